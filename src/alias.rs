@@ -1,4 +1,6 @@
+use std::collections::HashMap;
 use std::rc::Rc;
 
 pub type Str = Box<str>;
 pub type Name = Rc<str>;
+pub type Labels = HashMap<Str, usize>;
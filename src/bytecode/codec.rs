@@ -0,0 +1,552 @@
+use std::rc::Rc;
+
+use crate::alias::Str;
+use crate::error::BytecodeError;
+use crate::lexer::{Literal, Span};
+use crate::runtime::{
+    BinOperator, Conversion, Element, Flag, Instruction, Label, Operand, RegisterName, Scope, Slot,
+    Struct, Type, UnOperator, Value, Variable,
+};
+
+use super::reader::Reader;
+use super::writer::Writer;
+
+pub trait Codec: Sized {
+    fn encode(&self, w: &mut Writer);
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError>;
+}
+
+fn invalid<T>(what: &str, tag: u8) -> Result<T, BytecodeError> {
+    Err(BytecodeError::Invalid(
+        format!("unknown {} tag {}", what, tag).into(),
+    ))
+}
+
+// enums without fields are stored as their position in this list, so
+// variants may only ever be appended
+macro_rules! fieldless {
+    ($type:ident, [$($variant:ident),* $(,)?]) => {
+        impl Codec for $type {
+            fn encode(&self, w: &mut Writer) {
+                const ALL: &[$type] = &[$($type::$variant),*];
+                let tag = ALL.iter().position(|i| i == self).expect("every variant is listed");
+
+                w.u8(tag as u8);
+            }
+
+            fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+                const ALL: &[$type] = &[$($type::$variant),*];
+                let tag = r.u8()?;
+
+                match ALL.get(tag as usize) {
+                    Some(i) => Ok(*i),
+                    None => invalid(stringify!($type), tag),
+                }
+            }
+        }
+    };
+}
+
+fieldless!(
+    RegisterName,
+    [
+        A0, A1, A2, A3, B0, B1, B2, B3, C0, C1, C2, C3, D0, D1, D2, D3, F0, F1, F2, F3, G0, G1, G2,
+        G3, I0, I1, I2, I3,
+    ]
+);
+
+fieldless!(
+    BinOperator,
+    [
+        Add,
+        Subtract,
+        Multiply,
+        Divide,
+        Modulus,
+        LeftShift,
+        RightShift,
+        And,
+        Or,
+        Xor,
+        Equals,
+        NotEquals,
+        LessThan,
+        GreaterThan,
+        LessThanEqualTo,
+        GreaterThanEqualTo,
+        SignedAdd,
+        SignedSubtract,
+        SignedMultiply,
+        SignedDivide,
+        SignedModulus,
+        ArithmeticRightShift,
+        SignedLessThan,
+        SignedGreaterThan,
+        SignedLessThanEqualTo,
+        SignedGreaterThanEqualTo,
+        WrappingAdd,
+        WrappingSubtract,
+        WrappingMultiply,
+        WrappingLeftShift,
+        SaturatingAdd,
+        SaturatingSubtract,
+        SaturatingMultiply,
+        SaturatingLeftShift,
+        AddCarry,
+        SubtractBorrow,
+    ]
+);
+
+fieldless!(
+    UnOperator,
+    [
+        Not,
+        Increment,
+        Decrement,
+        SignedIncrement,
+        SignedDecrement,
+        WrappingIncrement,
+        WrappingDecrement,
+        SaturatingIncrement,
+        SaturatingDecrement,
+    ]
+);
+
+fieldless!(
+    Conversion,
+    [
+        ZeroExtend,
+        SignExtend,
+        Truncate,
+        IntToFloat,
+        SignedIntToFloat,
+        FloatToInt,
+        FloatToSignedInt,
+        FloatToFloat,
+        BoolToInt,
+        IntToBool,
+    ]
+);
+
+fieldless!(Flag, [Zero, Carry, Overflow, Sign]);
+
+fieldless!(Scope, [Global, Local]);
+
+impl Codec for usize {
+    fn encode(&self, w: &mut Writer) {
+        w.usize(*self);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        r.usize()
+    }
+}
+
+impl Codec for Str {
+    fn encode(&self, w: &mut Writer) {
+        w.str(self);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        r.str()
+    }
+}
+
+impl Codec for Rc<str> {
+    fn encode(&self, w: &mut Writer) {
+        w.str(self);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        Ok(r.str()?.into())
+    }
+}
+
+impl<T: Codec> Codec for Option<T> {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            None => w.u8(0),
+            Some(i) => {
+                w.u8(1);
+                i.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        match r.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(r)?)),
+            tag => invalid("Option", tag),
+        }
+    }
+}
+
+impl Codec for Span {
+    fn encode(&self, w: &mut Writer) {
+        w.usize(self.line);
+        w.usize(self.column);
+        w.usize(self.start);
+        w.usize(self.end);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        Ok(Span::new(r.usize()?, r.usize()?, r.usize()?, r.usize()?))
+    }
+}
+
+impl Codec for Type {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Type::Int8 => w.u8(0),
+            Type::Int16 => w.u8(1),
+            Type::Int32 => w.u8(2),
+            Type::Int64 => w.u8(3),
+            Type::Uint8 => w.u8(4),
+            Type::Uint16 => w.u8(5),
+            Type::Uint32 => w.u8(6),
+            Type::Uint64 => w.u8(7),
+            Type::Float32 => w.u8(8),
+            Type::Float64 => w.u8(9),
+            Type::Boolean => w.u8(10),
+            Type::Character => w.u8(11),
+            // structs are written out in full wherever they appear, they are small
+            Type::Struct(i) => {
+                w.u8(12);
+                w.str(&i.name);
+                w.usize(i.fields.len());
+
+                for field in &i.fields {
+                    w.str(&field.name);
+                    field.field_type.encode(w);
+                }
+            }
+            Type::Array(i, len) => {
+                w.u8(13);
+                i.encode(w);
+                w.usize(*len);
+            }
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        decode_type(r, 0)
+    }
+}
+
+// types nest, the limit keeps a hostile file from overflowing the stack
+const MAX_TYPE_DEPTH: usize = 1024;
+
+fn decode_type(r: &mut Reader, depth: usize) -> Result<Type, BytecodeError> {
+    if depth > MAX_TYPE_DEPTH {
+        return Err(BytecodeError::Invalid("types are nested too deeply".into()));
+    }
+
+    Ok(match r.u8()? {
+        0 => Type::Int8,
+        1 => Type::Int16,
+        2 => Type::Int32,
+        3 => Type::Int64,
+        4 => Type::Uint8,
+        5 => Type::Uint16,
+        6 => Type::Uint32,
+        7 => Type::Uint64,
+        8 => Type::Float32,
+        9 => Type::Float64,
+        10 => Type::Boolean,
+        11 => Type::Character,
+        12 => {
+            let name = r.str()?;
+            let mut fields = Vec::new();
+
+            for _ in 0..r.usize()? {
+                fields.push((r.str()?, decode_type(r, depth + 1)?));
+            }
+
+            Type::Struct(Rc::new(Struct::new(name, fields)))
+        }
+        13 => Type::Array(Box::new(decode_type(r, depth + 1)?), r.usize()?),
+        tag => return invalid("Type", tag),
+    })
+}
+
+impl Codec for Literal {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Literal::Boolean(i) => {
+                w.u8(0);
+                w.u8(*i as u8);
+            }
+            Literal::Float(i) => {
+                w.u8(1);
+                w.raw(&i.to_le_bytes());
+            }
+            Literal::Integer(i, suffix) => {
+                w.u8(2);
+                w.raw(&i.to_le_bytes());
+                suffix.encode(w);
+            }
+            Literal::Character(i) => {
+                w.u8(3);
+                w.u64(*i as u64);
+            }
+            Literal::String(i) => {
+                w.u8(4);
+                w.str(i);
+            }
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        Ok(match r.u8()? {
+            0 => Literal::Boolean(r.u8()? != 0),
+            1 => Literal::Float(f64::from_le_bytes(array(r.raw(8)?))),
+            2 => Literal::Integer(i128::from_le_bytes(array(r.raw(16)?)), Option::decode(r)?),
+            3 => {
+                let i = r.u64()?;
+                let i = u32::try_from(i).ok().and_then(char::from_u32).ok_or(
+                    BytecodeError::Invalid(format!("{} is not a character", i).into()),
+                )?;
+
+                Literal::Character(i)
+            }
+            4 => Literal::String(r.str()?),
+            tag => return invalid("Literal", tag),
+        })
+    }
+}
+
+impl Codec for Slot {
+    fn encode(&self, w: &mut Writer) {
+        self.scope.encode(w);
+        w.usize(self.offset);
+        self.var_type.encode(w);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        Ok(Slot {
+            scope: Scope::decode(r)?,
+            offset: r.usize()?,
+            var_type: Type::decode(r)?,
+        })
+    }
+}
+
+// compiled programs only hold slots, names are kept so that any `Program` round trips
+impl Codec for Variable {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Variable::Slot(i) => {
+                w.u8(0);
+                i.encode(w);
+            }
+            Variable::Name(name, fields, span) => {
+                w.u8(1);
+                name.encode(w);
+                w.usize(fields.len());
+
+                for i in fields {
+                    w.str(i);
+                }
+
+                span.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        match r.u8()? {
+            0 => Ok(Variable::Slot(Slot::decode(r)?)),
+            1 => {
+                let name = Codec::decode(r)?;
+                let mut fields = Vec::new();
+
+                for _ in 0..r.usize()? {
+                    fields.push(r.str()?);
+                }
+
+                Ok(Variable::Name(name, fields, Span::decode(r)?))
+            }
+            tag => invalid("Variable", tag),
+        }
+    }
+}
+
+impl Codec for Label {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Label::Target(i) => {
+                w.u8(0);
+                w.usize(*i);
+            }
+            Label::Name(name, span) => {
+                w.u8(1);
+                w.str(name);
+                span.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        match r.u8()? {
+            0 => Ok(Label::Target(r.usize()?)),
+            1 => Ok(Label::Name(r.str()?, Span::decode(r)?)),
+            tag => invalid("Label", tag),
+        }
+    }
+}
+
+impl Codec for Value {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Value::RValue(i) => {
+                w.u8(0);
+                i.encode(w);
+            }
+            Value::LValue(i) => {
+                w.u8(1);
+                i.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        match r.u8()? {
+            0 => Ok(Value::RValue(Literal::decode(r)?)),
+            1 => Ok(Value::LValue(Variable::decode(r)?)),
+            tag => invalid("Value", tag),
+        }
+    }
+}
+
+impl Codec for Operand {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Operand::Register(i) => {
+                w.u8(0);
+                i.encode(w);
+            }
+            Operand::Immediate(i) => {
+                w.u8(1);
+                w.u64(*i);
+            }
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        match r.u8()? {
+            0 => Ok(Operand::Register(RegisterName::decode(r)?)),
+            1 => Ok(Operand::Immediate(r.u64()?)),
+            tag => invalid("Operand", tag),
+        }
+    }
+}
+
+impl Codec for Element {
+    fn encode(&self, w: &mut Writer) {
+        self.name.encode(w);
+        self.array.encode(w);
+        self.index.encode(w);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        Ok(Element {
+            name: Codec::decode(r)?,
+            array: Variable::decode(r)?,
+            index: Operand::decode(r)?,
+        })
+    }
+}
+
+// writes the tag, then every field in order
+macro_rules! fields {
+    ($w:ident, $tag:expr $(, $field:expr)*) => {{
+        $w.u8($tag);
+        $($field.encode($w);)*
+    }};
+}
+
+impl Codec for Instruction {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Instruction::Push(a, b) => fields!(w, 0, a, b),
+            Instruction::Set(a, b) => fields!(w, 1, a, b),
+            Instruction::Load(a, b) => fields!(w, 2, a, b),
+            Instruction::Unload(a, b) => fields!(w, 3, a, b),
+            Instruction::LoadElement(a, b) => fields!(w, 4, a, b),
+            Instruction::UnloadElement(a, b) => fields!(w, 5, a, b),
+            Instruction::Move(a, b) => fields!(w, 6, a, b),
+            Instruction::AddressOf(a, b) => fields!(w, 7, a, b),
+            Instruction::LoadPointer(a, b) => fields!(w, 8, a, b),
+            Instruction::StorePointer(a, b) => fields!(w, 9, a, b),
+            Instruction::Alloc(a, b) => fields!(w, 10, a, b),
+            Instruction::Free(a) => fields!(w, 11, a),
+            Instruction::Realloc(a, b) => fields!(w, 12, a, b),
+            Instruction::Print(a) => fields!(w, 13, a),
+            Instruction::PrintRegister(a, b) => fields!(w, 14, a, b),
+            Instruction::PrintString(a) => fields!(w, 15, a),
+            Instruction::Read(a) => fields!(w, 16, a),
+            Instruction::ReadRegister(a, b) => fields!(w, 17, a, b),
+            Instruction::ReadLine(a) => fields!(w, 18, a),
+            Instruction::Convert(a, b, c) => fields!(w, 19, a, b, c),
+            Instruction::Function(a, b) => fields!(w, 20, a, b),
+            Instruction::Return => fields!(w, 21),
+            Instruction::Call(a) => fields!(w, 22, a),
+            Instruction::Extern(a) => fields!(w, 23, a),
+            Instruction::CallIf(a, b) => fields!(w, 24, a, b),
+            Instruction::Jump(a) => fields!(w, 25, a),
+            Instruction::JumpIf(a, b) => fields!(w, 26, a, b),
+            Instruction::JumpFlag(a, b) => fields!(w, 27, a, b),
+            Instruction::Exit(a) => fields!(w, 28, a),
+            Instruction::BinaryOp(a, b, c, d) => fields!(w, 29, a, b, c, d),
+            Instruction::UnaryOp(a, b, c) => fields!(w, 30, a, b, c),
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, BytecodeError> {
+        fn d<T: Codec>(r: &mut Reader) -> Result<T, BytecodeError> {
+            T::decode(r)
+        }
+
+        Ok(match r.u8()? {
+            0 => Instruction::Push(d(r)?, d(r)?),
+            1 => Instruction::Set(d(r)?, d(r)?),
+            2 => Instruction::Load(d(r)?, d(r)?),
+            3 => Instruction::Unload(d(r)?, d(r)?),
+            4 => Instruction::LoadElement(d(r)?, d(r)?),
+            5 => Instruction::UnloadElement(d(r)?, d(r)?),
+            6 => Instruction::Move(d(r)?, d(r)?),
+            7 => Instruction::AddressOf(d(r)?, d(r)?),
+            8 => Instruction::LoadPointer(d(r)?, d(r)?),
+            9 => Instruction::StorePointer(d(r)?, d(r)?),
+            10 => Instruction::Alloc(d(r)?, d(r)?),
+            11 => Instruction::Free(d(r)?),
+            12 => Instruction::Realloc(d(r)?, d(r)?),
+            13 => Instruction::Print(d(r)?),
+            14 => Instruction::PrintRegister(d(r)?, d(r)?),
+            15 => Instruction::PrintString(d(r)?),
+            16 => Instruction::Read(d(r)?),
+            17 => Instruction::ReadRegister(d(r)?, d(r)?),
+            18 => Instruction::ReadLine(d(r)?),
+            19 => Instruction::Convert(d(r)?, d(r)?, d(r)?),
+            20 => Instruction::Function(d(r)?, d(r)?),
+            21 => Instruction::Return,
+            22 => Instruction::Call(d(r)?),
+            23 => Instruction::Extern(d(r)?),
+            24 => Instruction::CallIf(d(r)?, d(r)?),
+            25 => Instruction::Jump(d(r)?),
+            26 => Instruction::JumpIf(d(r)?, d(r)?),
+            27 => Instruction::JumpFlag(d(r)?, d(r)?),
+            28 => Instruction::Exit(d(r)?),
+            29 => Instruction::BinaryOp(d(r)?, d(r)?, d(r)?, d(r)?),
+            30 => Instruction::UnaryOp(d(r)?, d(r)?, d(r)?),
+            tag => return invalid("Instruction", tag),
+        })
+    }
+}
+
+fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    bytes
+        .try_into()
+        .expect("the reader returns exactly N bytes")
+}
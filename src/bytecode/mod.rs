@@ -0,0 +1,279 @@
+// the on-disk format of a compiled program:
+//
+//     magic     4 bytes  "JAPC"
+//     version   u16 LE
+//     checksum  u32 LE   FNV-1a of the payload
+//     payload            string pool, data segment, globals, labels, instructions
+
+mod codec;
+mod reader;
+mod validate;
+mod writer;
+
+use codec::Codec;
+use reader::Reader;
+use writer::Writer;
+
+use crate::error::BytecodeError;
+use crate::runtime::{Instruction, Program, Slot};
+
+pub const MAGIC: &[u8; 4] = b"JAPC";
+// bump whenever the encoding of anything in the payload changes
+pub const VERSION: u16 = 2;
+
+const HEADER_SIZE: usize = 10;
+
+pub fn is_bytecode(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+pub fn encode(program: &Program) -> Vec<u8> {
+    let mut w = Writer::default();
+
+    w.bytes(&program.data);
+    w.usize(program.globals_size);
+
+    // sorted so that building the same source always gives the same file
+    let mut globals: Vec<_> = program.globals.iter().collect();
+    globals.sort_by(|a, b| a.0.cmp(b.0));
+    w.usize(globals.len());
+
+    for (name, slot) in globals {
+        name.encode(&mut w);
+        slot.encode(&mut w);
+    }
+
+    let mut labels: Vec<_> = program.labels.iter().collect();
+    labels.sort();
+    w.usize(labels.len());
+
+    for (name, target) in labels {
+        w.str(name);
+        w.usize(*target);
+    }
+
+    w.usize(program.instructions.len());
+
+    for i in &program.instructions {
+        i.encode(&mut w);
+    }
+
+    let payload = w.finish();
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&checksum(&payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Program, BytecodeError> {
+    if !is_bytecode(bytes) {
+        return Err(BytecodeError::NotBytecode);
+    }
+
+    if bytes.len() < HEADER_SIZE {
+        return Err(BytecodeError::Truncated);
+    }
+
+    // checked before the checksum, a newer file is not a corrupt one
+    let found = u16::from_le_bytes([bytes[4], bytes[5]]);
+
+    if found != VERSION {
+        return Err(BytecodeError::Version {
+            found,
+            expected: VERSION,
+        });
+    }
+
+    let sum = u32::from_le_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]);
+    let payload = &bytes[HEADER_SIZE..];
+
+    if checksum(payload) != sum {
+        return Err(BytecodeError::Checksum);
+    }
+
+    let mut r = Reader::new(payload)?;
+    let mut program = Program {
+        data: r.bytes()?.to_vec(),
+        globals_size: r.usize()?,
+        ..Program::default()
+    };
+
+    for _ in 0..r.usize()? {
+        let name = Codec::decode(&mut r)?;
+        program.globals.insert(name, Slot::decode(&mut r)?);
+    }
+
+    for _ in 0..r.usize()? {
+        let name = r.str()?;
+        program.labels.insert(name, r.usize()?);
+    }
+
+    for _ in 0..r.usize()? {
+        program.instructions.push(Instruction::decode(&mut r)?);
+    }
+
+    if !r.is_empty() {
+        return Err(BytecodeError::Invalid(
+            "trailing bytes after the instructions".into(),
+        ));
+    }
+
+    validate::validate(&program)?;
+    Ok(program)
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, i| {
+        (hash ^ *i as u32).wrapping_mul(0x01000193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Literal;
+    use crate::runtime::{Label, RegisterName, Scope, Type, Value, Variable};
+    use crate::Vm;
+
+    const SOURCE: &str = "struct Point
+int32 x
+int32 y
+end
+push int32 total
+push Point pt
+push int32[3] xs
+fn bump
+push int32 n
+load 5 c0
+unload c0 n
+load total c1
+load n c2
+add c1 c2 c1
+unload c1 total
+end
+load 2 c0
+unload c0 pt.x
+load 3 c0
+unload c0 xs[1]
+load 0 c3
+again:
+call bump
+add c3 1 c3
+eq c3 2 i0
+jumpif done i0
+jump again
+done:
+load total c0
+load pt.x c1
+add c0 c1 c0
+load xs[1] c1
+add c0 c1 c0
+unload c0 total
+load \"hi\" d0";
+
+    fn compiled() -> Vec<u8> {
+        encode(&crate::compile(SOURCE).unwrap())
+    }
+
+    #[test]
+    fn round_trips() {
+        let bytes = compiled();
+        let program = decode(&bytes).unwrap();
+
+        assert_eq!(encode(&program), bytes);
+
+        let mut vm = Vm::new(program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(vm.get::<i32>("total"), Ok(15));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut bytes = compiled();
+        bytes[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+
+        assert_eq!(
+            decode(&bytes).unwrap_err(),
+            BytecodeError::Version {
+                found: VERSION + 1,
+                expected: VERSION
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_corrupt_files() {
+        let bytes = compiled();
+
+        let mut corrupt = bytes.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert_eq!(decode(&corrupt).unwrap_err(), BytecodeError::Checksum);
+
+        assert_eq!(decode(&bytes[..7]).unwrap_err(), BytecodeError::Truncated);
+        assert_eq!(decode(b"push").unwrap_err(), BytecodeError::NotBytecode);
+    }
+
+    #[test]
+    // `encode` writes a valid header and checksum for anything, as a hand made file would
+    fn rejects_programs_the_parser_would_not_make() {
+        let mut program = Program::default();
+        program
+            .instructions
+            .push(Instruction::Exit(Some(RegisterName::F0)));
+        assert!(matches!(
+            decode(&encode(&program)),
+            Err(BytecodeError::Invalid(_))
+        ));
+
+        let mut program = Program::default();
+        program.instructions.push(Instruction::Push(
+            Type::Int32,
+            Variable::Slot(Slot {
+                scope: Scope::Global,
+                offset: usize::MAX,
+                var_type: Type::Int32,
+            }),
+        ));
+        assert!(matches!(
+            decode(&encode(&program)),
+            Err(BytecodeError::Invalid(_))
+        ));
+
+        let mut program = Program::default();
+        program.instructions.push(Instruction::Function(7, 0));
+        assert!(matches!(
+            decode(&encode(&program)),
+            Err(BytecodeError::Invalid(_))
+        ));
+
+        let mut program = Program::default();
+        let slot = Slot {
+            scope: Scope::Global,
+            offset: 0,
+            var_type: Type::Uint8,
+        };
+        program.globals_size = 1;
+        program.instructions.push(Instruction::Set(
+            Variable::Slot(slot),
+            Value::RValue(Literal::Integer(-1, None)),
+        ));
+        assert!(matches!(
+            decode(&encode(&program)),
+            Err(BytecodeError::Invalid(_))
+        ));
+
+        let mut program = Program::default();
+        program
+            .instructions
+            .push(Instruction::Jump(Label::Target(2)));
+        assert!(matches!(
+            decode(&encode(&program)),
+            Err(BytecodeError::Invalid(_))
+        ));
+    }
+}
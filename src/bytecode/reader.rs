@@ -0,0 +1,83 @@
+use crate::alias::Str;
+use crate::error::BytecodeError;
+
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    strings: Vec<Str>,
+}
+
+impl<'a> Reader<'a> {
+    // reads the string pool at the start of `bytes`
+    pub fn new(bytes: &'a [u8]) -> Result<Self, BytecodeError> {
+        let mut reader = Self {
+            bytes,
+            pos: 0,
+            strings: Vec::new(),
+        };
+
+        for _ in 0..reader.usize()? {
+            let string = std::str::from_utf8(reader.bytes()?)
+                .map_err(|_| BytecodeError::Invalid("string is not valid UTF-8".into()))?;
+
+            reader.strings.push(string.into());
+        }
+
+        Ok(reader)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+
+    pub fn u8(&mut self) -> Result<u8, BytecodeError> {
+        Ok(self.raw(1)?[0])
+    }
+
+    pub fn u64(&mut self) -> Result<u64, BytecodeError> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(BytecodeError::Invalid("integer is too long".into()))
+    }
+
+    pub fn usize(&mut self) -> Result<usize, BytecodeError> {
+        usize::try_from(self.u64()?)
+            .map_err(|_| BytecodeError::Invalid("integer does not fit in usize".into()))
+    }
+
+    pub fn raw(&mut self, len: usize) -> Result<&'a [u8], BytecodeError> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.pos..end))
+            .ok_or(BytecodeError::Truncated)?;
+
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], BytecodeError> {
+        let len = self.usize()?;
+        self.raw(len)
+    }
+
+    pub fn str(&mut self) -> Result<Str, BytecodeError> {
+        let index = self.usize()?;
+
+        self.strings
+            .get(index)
+            .cloned()
+            .ok_or(BytecodeError::Invalid(
+                format!("no string {} in the pool", index).into(),
+            ))
+    }
+}
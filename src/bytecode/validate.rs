@@ -0,0 +1,247 @@
+use crate::error::BytecodeError;
+use crate::runtime::{
+    Element, Instruction, Label, Operand, Program, RegisterClass, Scope, Type, Value, Variable,
+    MAX_TYPE_SIZE,
+};
+
+// a decoded program has only passed the format checks, this makes sure it also holds
+// everything the parser guarantees so that running it cannot panic the vm
+pub fn validate(program: &Program) -> Result<(), BytecodeError> {
+    let instructions = &program.instructions;
+    let len = instructions.len();
+
+    if program
+        .data
+        .len()
+        .checked_add(program.globals_size)
+        .is_none()
+    {
+        return Err(invalid("the globals do not fit in memory"));
+    }
+
+    for (name, slot) in &program.globals {
+        if slot.scope != Scope::Global {
+            return Err(invalid(format!("global {} has a local slot", name)));
+        }
+
+        check_type(&slot.var_type)?;
+        check_fits(slot.offset, &slot.var_type, program.globals_size)?;
+    }
+
+    for (name, target) in &program.labels {
+        if *target > len {
+            return Err(invalid(format!("label {} is past the end", name)));
+        }
+    }
+
+    let owners = owners(instructions)?;
+
+    for (pc, instruction) in instructions.iter().enumerate() {
+        let at = |e: BytecodeError| match e {
+            BytecodeError::Invalid(i) => invalid(format!("{} at pc {}", i, pc)),
+            e => e,
+        };
+
+        // locals are only valid inside the frame of the function they are in
+        let frame_size = owners[pc].map(|start| match instructions[start] {
+            Instruction::Function(_, size) => size,
+            _ => unreachable!("owners only holds functions"),
+        });
+
+        if let Instruction::Push(var_type, _) = instruction {
+            check_type(var_type).map_err(at)?;
+        }
+
+        for var in variables(instruction) {
+            check_variable(var, program.globals_size, frame_size).map_err(at)?;
+        }
+
+        if let Instruction::Set(Variable::Slot(slot), Value::RValue(literal)) = instruction {
+            literal
+                .check_type(&slot.var_type)
+                .map_err(|e| at(invalid(e)))?;
+        }
+
+        check_registers(instruction).map_err(at)?;
+
+        let (label, is_call) = match instruction {
+            Instruction::Call(label) | Instruction::CallIf(label, _) => (label, true),
+            Instruction::Jump(label)
+            | Instruction::JumpIf(label, _)
+            | Instruction::JumpFlag(label, _) => (label, false),
+            _ => continue,
+        };
+
+        let Label::Target(target) = label else {
+            return Err(at(invalid("unresolved label")));
+        };
+
+        // the same rules the link pass applies to labels
+        if is_call {
+            if !matches!(instructions.get(*target), Some(Instruction::Function(..))) {
+                return Err(at(invalid("call to an index that is not a fn")));
+            }
+        } else if *target > len || owners.get(*target).copied().flatten() != owners[pc] {
+            return Err(at(invalid("jump across a function boundary")));
+        }
+    }
+
+    Ok(())
+}
+
+fn invalid(message: impl Into<Box<str>>) -> BytecodeError {
+    BytecodeError::Invalid(message.into())
+}
+
+// the `fn` each instruction is in, and a check that every body ends in its `end`
+fn owners(instructions: &[Instruction]) -> Result<Vec<Option<usize>>, BytecodeError> {
+    let mut owners = vec![None; instructions.len()];
+
+    for (start, instruction) in instructions.iter().enumerate() {
+        let Instruction::Function(body_end, _) = instruction else {
+            continue;
+        };
+
+        if *body_end <= start || !matches!(instructions.get(*body_end), Some(Instruction::Return)) {
+            return Err(invalid(format!("fn at pc {} has no end", start)));
+        }
+
+        for pc in start + 1..=*body_end {
+            if let Instruction::Function(..) = instructions[pc] {
+                return Err(invalid(format!("fn at pc {} is nested", pc)));
+            }
+
+            owners[pc] = Some(start);
+        }
+    }
+
+    Ok(owners)
+}
+
+fn variables(instruction: &Instruction) -> Vec<&Variable> {
+    match instruction {
+        Instruction::Set(var, Value::LValue(value)) => vec![var, value],
+        Instruction::Push(_, var)
+        | Instruction::Set(var, _)
+        | Instruction::Unload(_, var)
+        | Instruction::AddressOf(var, _)
+        | Instruction::Read(var)
+        | Instruction::ReadLine(var)
+        | Instruction::Load(Value::LValue(var), _)
+        | Instruction::Print(Value::LValue(var)) => vec![var],
+        Instruction::LoadElement(element, _) | Instruction::UnloadElement(_, element) => {
+            vec![&element.array]
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn check_variable(
+    var: &Variable,
+    globals_size: usize,
+    frame_size: Option<usize>,
+) -> Result<(), BytecodeError> {
+    let Variable::Slot(slot) = var else {
+        return Err(invalid("unresolved variable"));
+    };
+
+    check_type(&slot.var_type)?;
+
+    match (slot.scope, frame_size) {
+        (Scope::Global, _) => check_fits(slot.offset, &slot.var_type, globals_size),
+        (Scope::Local, Some(size)) => check_fits(slot.offset, &slot.var_type, size),
+        (Scope::Local, None) => Err(invalid("local variable outside a fn")),
+    }
+}
+
+fn check_fits(offset: usize, var_type: &Type, frame_size: usize) -> Result<(), BytecodeError> {
+    match offset.checked_add(var_type.size()) {
+        Some(end) if end <= frame_size => Ok(()),
+        _ => Err(invalid("slot is outside its frame")),
+    }
+}
+
+// the parser never makes a type without a size, nor one larger than the maximum
+fn check_type(var_type: &Type) -> Result<(), BytecodeError> {
+    match var_type {
+        Type::Struct(i) => {
+            for field in &i.fields {
+                check_type(&field.field_type)?;
+            }
+        }
+        Type::Array(i, _) => check_type(i)?,
+        _ => (),
+    }
+
+    if var_type.size() == 0 || var_type.size() > MAX_TYPE_SIZE {
+        return Err(invalid(format!("{:?} has an invalid size", var_type)));
+    }
+
+    Ok(())
+}
+
+// the register classes the parser insists on, anything else is checked when it runs
+fn check_registers(instruction: &Instruction) -> Result<(), BytecodeError> {
+    let pointers = match instruction {
+        Instruction::AddressOf(_, reg)
+        | Instruction::LoadPointer(reg, _)
+        | Instruction::StorePointer(_, reg)
+        | Instruction::Free(reg)
+        | Instruction::PrintString(reg) => vec![*reg],
+        Instruction::Alloc(a, b) | Instruction::Realloc(a, b) => vec![*a, *b],
+        _ => Vec::new(),
+    };
+
+    if pointers
+        .iter()
+        .any(|i| i.register_class() != RegisterClass::D)
+    {
+        return Err(invalid("address in a register other than d"));
+    }
+
+    let integer = match instruction {
+        Instruction::Exit(Some(reg)) => Some(*reg),
+        Instruction::LoadElement(
+            Element {
+                index: Operand::Register(reg),
+                ..
+            },
+            _,
+        )
+        | Instruction::UnloadElement(
+            _,
+            Element {
+                index: Operand::Register(reg),
+                ..
+            },
+        ) => Some(*reg),
+        _ => None,
+    };
+
+    if integer.is_some_and(|i| !i.register_class().is_integer()) {
+        return Err(invalid("integer operand in a non-integer register"));
+    }
+
+    let classes_match = match instruction {
+        Instruction::Move(src, dst) => src.register_class() == dst.register_class(),
+        Instruction::Convert(conversion, src, dst) => conversion.accepts(*src, *dst),
+        _ => true,
+    };
+
+    if !classes_match {
+        return Err(invalid("conversion between the wrong register classes"));
+    }
+
+    if let Instruction::PrintRegister(reg, value_type)
+    | Instruction::ReadRegister(reg, value_type) = instruction
+    {
+        if !reg.register_class().io_types().contains(value_type) {
+            return Err(invalid(format!(
+                "{:?} cannot be printed or read as {:?}",
+                reg, value_type
+            )));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use crate::alias::Str;
+
+// the body of a file, strings are collected into a pool and referenced by index
+#[derive(Default)]
+pub struct Writer {
+    bytes: Vec<u8>,
+    strings: Vec<Str>,
+    indices: HashMap<Str, usize>,
+}
+
+impl Writer {
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    // LEB128, most numbers in a program are small
+    pub fn u64(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+
+            if value == 0 {
+                self.bytes.push(byte);
+                return;
+            }
+
+            self.bytes.push(byte | 0x80);
+        }
+    }
+
+    pub fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    pub fn raw(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.usize(bytes.len());
+        self.raw(bytes);
+    }
+
+    pub fn str(&mut self, value: &str) {
+        let index = match self.indices.get(value) {
+            Some(i) => *i,
+            None => {
+                self.strings.push(value.into());
+                self.indices.insert(value.into(), self.strings.len() - 1);
+                self.strings.len() - 1
+            }
+        };
+
+        self.usize(index);
+    }
+
+    // the string pool followed by the body, the pool has to be read first
+    pub fn finish(self) -> Vec<u8> {
+        let mut pool = Writer::default();
+        pool.usize(self.strings.len());
+
+        for i in &self.strings {
+            pool.bytes(i.as_bytes());
+        }
+
+        pool.raw(&self.bytes);
+        pool.bytes
+    }
+}
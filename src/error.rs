@@ -1,18 +1,316 @@
-use crate::alias::Str;
+use crate::alias::{Name, Str};
+use crate::lexer::Span;
+use crate::runtime::{Instruction, Type};
 
 #[derive(Debug)]
 pub enum JAPLError {
-    InvalidIdentifier(Str),
-    InvalidArgument(Str),
+    InvalidIdentifier(Str, Span),
+    InvalidArgument(Str, Span),
+    UnterminatedComment(Span),
+    InvalidLiteral(Str, Span),
+    UndefinedLabel(Str, Span),
+    // the second span is where the label was first defined
+    DuplicateLabel(Str, Span, Span),
+    // a jump between the body of a function and code outside it
+    CrossFunctionJump(Str, Span),
+    CallToNonFunction(Str, Span),
+}
+
+impl JAPLError {
+    pub fn span(&self) -> Span {
+        match self {
+            JAPLError::InvalidIdentifier(_, span)
+            | JAPLError::InvalidArgument(_, span)
+            | JAPLError::UnterminatedComment(span)
+            | JAPLError::InvalidLiteral(_, span)
+            | JAPLError::UndefinedLabel(_, span)
+            | JAPLError::DuplicateLabel(_, span, _)
+            | JAPLError::CrossFunctionJump(_, span)
+            | JAPLError::CallToNonFunction(_, span) => *span,
+        }
+    }
+
+    // renders the error the way rustc does, with the offending line and a caret under the token
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let span = self.span();
+        let source_line = source
+            .lines()
+            .nth(span.line.saturating_sub(1))
+            .unwrap_or("");
+
+        let width = source
+            .get(span.start..span.end)
+            .map(|i| i.chars().count())
+            .unwrap_or(0)
+            .max(1);
+        let gutter = " ".repeat(span.line.to_string().len());
+
+        format!(
+            "error: {}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}{}",
+            self,
+            gutter,
+            file_name,
+            span.line,
+            span.column,
+            gutter,
+            span.line,
+            source_line,
+            gutter,
+            " ".repeat(span.column.saturating_sub(1)),
+            "^".repeat(width),
+        )
+    }
 }
 
 impl std::fmt::Display for JAPLError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            JAPLError::InvalidIdentifier(i) => write!(f, "Invalid identifier: {}", i),
-            JAPLError::InvalidArgument(i) => write!(f, "Invalid arguments passed: {}", i),
+            JAPLError::InvalidIdentifier(i, _) => write!(f, "Invalid identifier: {}", i),
+            JAPLError::InvalidArgument(i, _) => write!(f, "Invalid arguments passed: {}", i),
+            JAPLError::UnterminatedComment(_) => write!(f, "Unterminated block comment"),
+            JAPLError::InvalidLiteral(i, _) => write!(f, "Invalid literal: {}", i),
+            JAPLError::UndefinedLabel(i, _) => write!(f, "Undefined label: {}", i),
+            JAPLError::DuplicateLabel(i, _, first) => write!(
+                f,
+                "Duplicate label: {}, first defined at {}:{}",
+                i, first.line, first.column
+            ),
+            JAPLError::CrossFunctionJump(i, _) => {
+                write!(f, "Cannot jump to label {} across a function boundary", i)
+            }
+            JAPLError::CallToNonFunction(i, _) => {
+                write!(f, "Cannot call label {}, it is not a fn", i)
+            }
         }
     }
 }
 
 impl std::error::Error for JAPLError {}
+
+// every error found while compiling, and the stage that found them
+#[derive(Debug)]
+pub struct Diagnostics {
+    pub stage: Stage,
+    pub errors: Vec<JAPLError>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Stage {
+    Lexer,
+    Parser,
+}
+
+impl Diagnostics {
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        self.errors
+            .iter()
+            .map(|i| i.render(file_name, source))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+impl std::fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+// every `extern` call to a function the host does not provide
+#[derive(Debug)]
+pub struct LinkError {
+    pub missing: Vec<MissingFunction>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MissingFunction {
+    pub pc: usize,
+    pub name: Str,
+}
+
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, function) in self.missing.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{}", function)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for MissingFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown host function `{}` at pc {}", self.name, self.pc)
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+// a compiled program that could not be loaded
+#[derive(Debug, PartialEq, Eq)]
+pub enum BytecodeError {
+    NotBytecode,
+    Version { found: u16, expected: u16 },
+    Checksum,
+    Truncated,
+    Invalid(Str),
+}
+
+impl std::fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotBytecode => write!(f, "not a japl bytecode file"),
+            Self::Version { found, expected } => write!(
+                f,
+                "bytecode version {} is not supported, this build reads version {}",
+                found, expected
+            ),
+            Self::Checksum => write!(f, "checksum mismatch, the file is corrupt"),
+            Self::Truncated => write!(f, "unexpected end of bytecode"),
+            Self::Invalid(i) => write!(f, "invalid bytecode: {}", i),
+        }
+    }
+}
+
+impl std::error::Error for BytecodeError {}
+
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub pc: usize,
+    pub instruction: Box<Instruction>,
+    pub cause: Cause,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cause {
+    UnknownVariable(Name),
+    UnknownLabel(Str),
+    RegisterMismatch,
+    SizeMismatch {
+        expected: usize,
+        found: usize,
+    },
+    IntegerOverflow,
+    DivisionByZero,
+    StackOverflow,
+    OutOfBounds {
+        start: usize,
+        end: usize,
+    },
+    IndexOutOfBounds {
+        array: Name,
+        index: u64,
+        len: usize,
+    },
+    NotAnArray(Name),
+    OutOfMemory(usize),
+    InvalidFree(usize),
+    DoubleFree {
+        address: usize,
+        allocated: usize,
+        freed: usize,
+    },
+    UseAfterFree {
+        address: usize,
+        allocated: usize,
+        freed: usize,
+    },
+    ReadOnly(usize),
+    InvalidLiteral(Str),
+    ReturnOutsideFunction,
+    InvalidInput(Str),
+    TypeMismatch {
+        expected: Type,
+        found: Type,
+    },
+    UnknownHostFunction(Str),
+    HostFunction(Str),
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at pc {} ({:?})",
+            self.cause, self.pc, self.instruction
+        )
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+impl std::fmt::Display for Cause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cause::UnknownVariable(i) => write!(f, "unknown variable `{}`", i),
+            Cause::UnknownLabel(i) => write!(f, "unknown label `{}`", i),
+            Cause::RegisterMismatch => write!(f, "register class mismatch"),
+            Cause::SizeMismatch { expected, found } => {
+                write!(
+                    f,
+                    "size mismatch: expected {} bytes, found {}",
+                    expected, found
+                )
+            }
+            Cause::IntegerOverflow => write!(f, "integer overflow"),
+            Cause::DivisionByZero => write!(f, "division by zero"),
+            Cause::StackOverflow => write!(f, "stack overflow"),
+            Cause::OutOfBounds { start, end } => {
+                write!(f, "memory access out of bounds: {}..{}", start, end)
+            }
+            Cause::IndexOutOfBounds { array, index, len } => write!(
+                f,
+                "index {} out of bounds for `{}` of length {}",
+                index, array, len
+            ),
+            Cause::NotAnArray(i) => write!(f, "`{}` is not an array", i),
+            Cause::OutOfMemory(i) => write!(f, "out of memory allocating {} bytes", i),
+            Cause::InvalidFree(i) => write!(f, "address {} was not returned by alloc", i),
+            Cause::DoubleFree {
+                address,
+                allocated,
+                freed,
+            } => write!(
+                f,
+                "double free of address {} allocated at pc {}, already freed at pc {}",
+                address, allocated, freed
+            ),
+            Cause::UseAfterFree {
+                address,
+                allocated,
+                freed,
+            } => write!(
+                f,
+                "use after free of address {} allocated at pc {}, freed at pc {}",
+                address, allocated, freed
+            ),
+            Cause::ReadOnly(i) => write!(f, "write to read-only memory at address {}", i),
+            Cause::InvalidLiteral(i) => write!(f, "{}", i),
+            Cause::ReturnOutsideFunction => write!(f, "`end` reached outside of a function call"),
+            Cause::InvalidInput(i) => write!(f, "invalid input: {}", i),
+            Cause::UnknownHostFunction(i) => write!(f, "unknown host function `{}`", i),
+            Cause::HostFunction(i) => write!(f, "host function failed: {}", i),
+            Cause::TypeMismatch { expected, found } => {
+                write!(
+                    f,
+                    "type mismatch: expected {:?}, found {:?}",
+                    expected, found
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use super::Span;
+
+pub struct Cursor<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    line: usize,
+    column: usize,
+    len: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(file: &'a str) -> Self {
+        Self {
+            chars: file.char_indices().peekable(),
+            line: 1,
+            column: 1,
+            len: file.len(),
+        }
+    }
+
+    // zero-width span at the next character
+    pub fn position(&mut self) -> Span {
+        let idx = self.chars.peek().map(|(idx, _)| *idx).unwrap_or(self.len);
+        Span::new(self.line, self.column, idx, idx)
+    }
+
+    pub fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    pub fn bump(&mut self) -> Option<char> {
+        let (_, cur_char) = self.chars.next()?;
+
+        if cur_char == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+
+        Some(cur_char)
+    }
+}
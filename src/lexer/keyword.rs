@@ -1,6 +1,4 @@
-use crate::error::JAPLError;
-
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Keyword {
     // body decleration
     Struct,
@@ -48,6 +46,38 @@ pub enum Keyword {
     LeftShift,
     RightShift,
 
+    // signed
+    SignedAdd,
+    SignedSubtract,
+    SignedMultiply,
+    SignedDivide,
+    SignedModulus,
+    ArithmeticRightShift,
+    SignedLessThan,
+    SignedGreaterThan,
+    SignedLessThanEqualTo,
+    SignedGreaterThanEqualTo,
+    SignedIncrement,
+    SignedDecrement,
+
+    // overflow
+    WrappingAdd,
+    WrappingSubtract,
+    WrappingMultiply,
+    WrappingIncrement,
+    WrappingDecrement,
+    WrappingLeftShift,
+    SaturatingAdd,
+    SaturatingSubtract,
+    SaturatingMultiply,
+    SaturatingIncrement,
+    SaturatingDecrement,
+    SaturatingLeftShift,
+
+    // carry
+    AddCarry,
+    SubtractBorrow,
+
     // statements
     Push,
     Set,
@@ -55,11 +85,50 @@ pub enum Keyword {
     Unload,
     Move,
 
+    // pointers
+    AddressOf,
+    LoadPointer,
+    StorePointer,
+
+    // heap
+    Alloc,
+    Free,
+    Realloc,
+
+    // input and output
+    Print,
+    PrintSigned,
+    PrintCharacter,
+    PrintString,
+    Read,
+    ReadSigned,
+    ReadCharacter,
+    ReadLine,
+
+    // conversion
+    ZeroExtend,
+    SignExtend,
+    Truncate,
+    IntToFloat,
+    SignedIntToFloat,
+    FloatToInt,
+    FloatToSignedInt,
+    FloatToFloat,
+    BoolToInt,
+    IntToBool,
+
     // control flow
     Call,
     CallIf,
+    Extern,
     Jump,
     JumpIf,
+    JumpZero,
+    JumpCarry,
+    JumpOverflow,
+    JumpSign,
+    Halt,
+    Exit,
 
     // Register
     RegClassA,
@@ -105,8 +174,104 @@ pub enum Keyword {
     RegisterI3,
 }
 
+impl Keyword {
+    pub fn starts_statement(&self) -> bool {
+        matches!(
+            self,
+            Keyword::Struct
+                | Keyword::Function
+                | Keyword::End
+                | Keyword::Add
+                | Keyword::Subtract
+                | Keyword::Multiply
+                | Keyword::Divide
+                | Keyword::Modulus
+                | Keyword::And
+                | Keyword::Or
+                | Keyword::Not
+                | Keyword::Xor
+                | Keyword::Equals
+                | Keyword::NotEquals
+                | Keyword::LessThan
+                | Keyword::GreaterThan
+                | Keyword::LessThanEqualTo
+                | Keyword::GreaterThanEqualTo
+                | Keyword::Increment
+                | Keyword::Decrement
+                | Keyword::LeftShift
+                | Keyword::RightShift
+                | Keyword::SignedAdd
+                | Keyword::SignedSubtract
+                | Keyword::SignedMultiply
+                | Keyword::SignedDivide
+                | Keyword::SignedModulus
+                | Keyword::ArithmeticRightShift
+                | Keyword::SignedLessThan
+                | Keyword::SignedGreaterThan
+                | Keyword::SignedLessThanEqualTo
+                | Keyword::SignedGreaterThanEqualTo
+                | Keyword::SignedIncrement
+                | Keyword::SignedDecrement
+                | Keyword::WrappingAdd
+                | Keyword::WrappingSubtract
+                | Keyword::WrappingMultiply
+                | Keyword::WrappingIncrement
+                | Keyword::WrappingDecrement
+                | Keyword::WrappingLeftShift
+                | Keyword::SaturatingAdd
+                | Keyword::SaturatingSubtract
+                | Keyword::SaturatingMultiply
+                | Keyword::SaturatingIncrement
+                | Keyword::SaturatingDecrement
+                | Keyword::SaturatingLeftShift
+                | Keyword::AddCarry
+                | Keyword::SubtractBorrow
+                | Keyword::Push
+                | Keyword::Set
+                | Keyword::Load
+                | Keyword::Unload
+                | Keyword::Move
+                | Keyword::AddressOf
+                | Keyword::LoadPointer
+                | Keyword::StorePointer
+                | Keyword::Alloc
+                | Keyword::Free
+                | Keyword::Realloc
+                | Keyword::Print
+                | Keyword::PrintSigned
+                | Keyword::PrintCharacter
+                | Keyword::PrintString
+                | Keyword::Read
+                | Keyword::ReadSigned
+                | Keyword::ReadCharacter
+                | Keyword::ReadLine
+                | Keyword::ZeroExtend
+                | Keyword::SignExtend
+                | Keyword::Truncate
+                | Keyword::IntToFloat
+                | Keyword::SignedIntToFloat
+                | Keyword::FloatToInt
+                | Keyword::FloatToSignedInt
+                | Keyword::FloatToFloat
+                | Keyword::BoolToInt
+                | Keyword::IntToBool
+                | Keyword::Call
+                | Keyword::CallIf
+                | Keyword::Extern
+                | Keyword::Jump
+                | Keyword::JumpIf
+                | Keyword::JumpZero
+                | Keyword::JumpCarry
+                | Keyword::JumpOverflow
+                | Keyword::JumpSign
+                | Keyword::Halt
+                | Keyword::Exit
+        )
+    }
+}
+
 impl TryFrom<&str> for Keyword {
-    type Error = JAPLError;
+    type Error = ();
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
@@ -150,10 +315,45 @@ impl TryFrom<&str> for Keyword {
             "unload" => Ok(Self::Unload),
             "move" => Ok(Self::Move),
 
+            "addr" => Ok(Self::AddressOf),
+            "loadp" => Ok(Self::LoadPointer),
+            "storep" => Ok(Self::StorePointer),
+
+            "alloc" => Ok(Self::Alloc),
+            "free" => Ok(Self::Free),
+            "realloc" => Ok(Self::Realloc),
+
+            "print" => Ok(Self::Print),
+            "sprint" => Ok(Self::PrintSigned),
+            "printc" => Ok(Self::PrintCharacter),
+            "prints" => Ok(Self::PrintString),
+            "read" => Ok(Self::Read),
+            "sread" => Ok(Self::ReadSigned),
+            "readc" => Ok(Self::ReadCharacter),
+            "readline" => Ok(Self::ReadLine),
+
+            "zext" => Ok(Self::ZeroExtend),
+            "sext" => Ok(Self::SignExtend),
+            "trunc" => Ok(Self::Truncate),
+            "itof" => Ok(Self::IntToFloat),
+            "sitof" => Ok(Self::SignedIntToFloat),
+            "ftoi" => Ok(Self::FloatToInt),
+            "ftosi" => Ok(Self::FloatToSignedInt),
+            "fcvt" => Ok(Self::FloatToFloat),
+            "btoi" => Ok(Self::BoolToInt),
+            "itob" => Ok(Self::IntToBool),
+
             "call" => Ok(Self::Call),
             "callif" => Ok(Self::CallIf),
+            "extern" => Ok(Self::Extern),
             "jump" => Ok(Self::Jump),
             "jumpif" => Ok(Self::JumpIf),
+            "jz" => Ok(Self::JumpZero),
+            "jc" => Ok(Self::JumpCarry),
+            "jo" => Ok(Self::JumpOverflow),
+            "js" => Ok(Self::JumpSign),
+            "halt" => Ok(Self::Halt),
+            "exit" => Ok(Self::Exit),
 
             "a" => Ok(Self::RegClassA),
             "a0" => Ok(Self::RegisterA0),
@@ -202,7 +402,36 @@ impl TryFrom<&str> for Keyword {
             "ls" => Ok(Self::LeftShift),
             "rs" => Ok(Self::RightShift),
 
-            _ => Err(JAPLError::InvalidIdentifier(value.into())),
+            "sadd" => Ok(Self::SignedAdd),
+            "ssub" => Ok(Self::SignedSubtract),
+            "smul" => Ok(Self::SignedMultiply),
+            "sdiv" => Ok(Self::SignedDivide),
+            "smod" => Ok(Self::SignedModulus),
+            "sar" => Ok(Self::ArithmeticRightShift),
+            "slt" => Ok(Self::SignedLessThan),
+            "sgt" => Ok(Self::SignedGreaterThan),
+            "sle" => Ok(Self::SignedLessThanEqualTo),
+            "sge" => Ok(Self::SignedGreaterThanEqualTo),
+            "sinc" => Ok(Self::SignedIncrement),
+            "sdec" => Ok(Self::SignedDecrement),
+
+            "addw" => Ok(Self::WrappingAdd),
+            "subw" => Ok(Self::WrappingSubtract),
+            "mulw" => Ok(Self::WrappingMultiply),
+            "incw" => Ok(Self::WrappingIncrement),
+            "decw" => Ok(Self::WrappingDecrement),
+            "lsw" => Ok(Self::WrappingLeftShift),
+            "addsat" => Ok(Self::SaturatingAdd),
+            "subsat" => Ok(Self::SaturatingSubtract),
+            "mulsat" => Ok(Self::SaturatingMultiply),
+            "incsat" => Ok(Self::SaturatingIncrement),
+            "decsat" => Ok(Self::SaturatingDecrement),
+            "lssat" => Ok(Self::SaturatingLeftShift),
+
+            "adc" => Ok(Self::AddCarry),
+            "sbb" => Ok(Self::SubtractBorrow),
+
+            _ => Err(()),
         }
     }
 }
@@ -1,26 +1,23 @@
-use crate::error::JAPLError;
+use std::num::IntErrorKind;
 
-#[derive(Debug, PartialEq)]
+use crate::alias::Str;
+use crate::runtime::Type;
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Literal {
     Boolean(bool),
     Float(f64),
-    Integer(u64),
+    Integer(i128, Option<Type>),
+    Character(char),
+    String(Str),
 }
 
 impl TryFrom<&str> for Literal {
-    type Error = JAPLError;
+    type Error = ();
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        if let Ok(i) = value.parse::<u64>() {
-            return Ok(Self::Integer(i));
-        }
-
-        if let Ok(i) = value.parse::<i64>() {
-            return Ok(Self::Integer(unsafe { std::mem::transmute::<i64, u64>(i) }));
-        }
-
-        if let Ok(i) = value.parse::<f64>() {
-            return Ok(Self::Float(i));
+        if let Ok(i) = Literal::parse_number(value) {
+            return Ok(i);
         }
 
         if value == "true" {
@@ -31,41 +28,273 @@ impl TryFrom<&str> for Literal {
             return Ok(Self::Boolean(false));
         }
 
-        if value.starts_with('\'') && value.ends_with('\'') {
-            todo!("Characters not supported yet");
+        Err(())
+    }
+}
+
+impl Literal {
+    // parses integers such as `-3`, `0xFF`, `0b1010_0001`, `0o17` or `255u8`, and floats
+    pub fn parse_number(value: &str) -> Result<Self, Str> {
+        let (negative, unsigned) = match value.strip_prefix('-') {
+            Some(i) => (true, i),
+            None => (false, value),
+        };
+
+        let (radix, body) = match unsigned.get(..2) {
+            Some("0x") | Some("0X") => (16, &unsigned[2..]),
+            Some("0b") | Some("0B") => (2, &unsigned[2..]),
+            Some("0o") | Some("0O") => (8, &unsigned[2..]),
+            _ => (10, unsigned),
+        };
+
+        let (digits, suffix) = match body.find(['u', 'i']) {
+            Some(i) => (&body[..i], Some(parse_suffix(&body[i..])?)),
+            None => (body, None),
+        };
+
+        let digits = digits.replace('_', "");
+        if digits.is_empty() {
+            return Err(format!("`{}` has no digits", value).into());
         }
 
-        if value.starts_with('"') && value.ends_with('"') {
-            todo!("Strings not supported yet");
+        let magnitude = match u128::from_str_radix(&digits, radix) {
+            Ok(i) => i,
+            // only text that looks like a float is one, a decimal that overflows is an error
+            Err(_) if radix == 10 && suffix.is_none() && digits.contains(['.', 'e', 'E']) => {
+                return digits
+                    .parse::<f64>()
+                    .map(|i| Self::Float(if negative { -i } else { i }))
+                    .map_err(|_| format!("`{}` is not a valid number", value).into())
+            }
+            Err(e) if *e.kind() == IntErrorKind::PosOverflow => {
+                return Err(format!("`{}` does not fit in 64 bits", value).into())
+            }
+            Err(_) if radix == 10 => {
+                return Err(format!("`{}` is not a valid number", value).into())
+            }
+            Err(_) => {
+                return Err(format!("`{}` is not a valid base {} integer", value, radix).into())
+            }
+        };
+
+        let integer = i128::try_from(magnitude)
+            .ok()
+            .map(|i| if negative { -i } else { i })
+            .filter(|i| *i >= i64::MIN as i128 && *i <= u64::MAX as i128)
+            .ok_or_else(|| format!("`{}` does not fit in 64 bits", value))?;
+
+        if let Some(suffix) = &suffix {
+            let (min, max) = integer_range(suffix.size(), suffix.is_signed());
+
+            if integer < min || integer > max {
+                return Err(format!("`{}` is out of range for {:?}", value, suffix).into());
+            }
         }
 
-        Err(JAPLError::InvalidIdentifier(value.into()))
+        Ok(Self::Integer(integer, suffix))
     }
-}
 
-impl Literal {
-    pub fn as_bytes(&self, size: usize) -> Vec<u8> {
+    // whether `set` may store the literal in a variable of `var_type` without changing its value
+    pub fn check_type(&self, var_type: &Type) -> Result<(), Str> {
+        let integer = matches!(
+            var_type,
+            Type::Int8
+                | Type::Int16
+                | Type::Int32
+                | Type::Int64
+                | Type::Uint8
+                | Type::Uint16
+                | Type::Uint32
+                | Type::Uint64
+        );
+
         match self {
+            Literal::Integer(i, suffix) if integer => {
+                if suffix.as_ref().is_some_and(|i| i != var_type) {
+                    return Err(
+                        format!("{:?} literal cannot be stored in {:?}", suffix, var_type).into(),
+                    );
+                }
+
+                let (min, max) = integer_range(var_type.size(), var_type.is_signed());
+
+                if *i < min || *i > max {
+                    return Err(format!(
+                        "integer literal `{}` is out of range for {:?}",
+                        i, var_type
+                    )
+                    .into());
+                }
+
+                Ok(())
+            }
+            Literal::Float(_) if matches!(var_type, Type::Float32 | Type::Float64) => Ok(()),
+            Literal::Boolean(_) if *var_type == Type::Boolean => Ok(()),
+            Literal::Character(_) if *var_type == Type::Character => Ok(()),
+            _ => Err(format!("{:?} cannot be stored in {:?}", self, var_type).into()),
+        }
+    }
+
+    pub fn as_bytes(&self, size: usize) -> Result<Vec<u8>, Str> {
+        match self {
+            Literal::Boolean(_) if size != 1 => {
+                Err(format!("a boolean literal cannot be stored in {} bytes", size).into())
+            }
             Literal::Boolean(i) => {
                 if *i {
-                    vec![1]
+                    Ok(vec![1])
                 } else {
-                    vec![0]
+                    Ok(vec![0])
                 }
             }
-            Literal::Float(i) => {
-                if size == 4 {
-                    (*i as f32).to_ne_bytes().to_vec()
-                } else {
-                    i.to_ne_bytes().to_vec()
+            Literal::Float(i) => match size {
+                4 => Ok((*i as f32).to_ne_bytes().to_vec()),
+                8 => Ok(i.to_ne_bytes().to_vec()),
+                _ => Err(format!("a float literal cannot be stored in {} bytes", size).into()),
+            },
+            Literal::Integer(i, suffix) => {
+                if let Some(suffix) = suffix {
+                    if suffix.size() != size {
+                        return Err(format!(
+                            "{:?} literal `{}` cannot be stored in {} bytes",
+                            suffix, i, size
+                        )
+                        .into());
+                    }
+                }
+
+                if ![1, 2, 4, 8].contains(&size) {
+                    return Err(
+                        format!("an integer literal cannot be stored in {} bytes", size).into(),
+                    );
                 }
+
+                // accepts both the signed and the unsigned range of the target size
+                let (min, _) = integer_range(size, true);
+                let (_, max) = integer_range(size, false);
+
+                if *i < min || *i > max {
+                    return Err(
+                        format!("integer literal `{}` does not fit in {} bytes", i, size).into(),
+                    );
+                }
+
+                Ok(match size {
+                    1 => (*i as u8).to_ne_bytes().to_vec(),
+                    2 => (*i as u16).to_ne_bytes().to_vec(),
+                    4 => (*i as u32).to_ne_bytes().to_vec(),
+                    _ => (*i as u64).to_ne_bytes().to_vec(),
+                })
             }
-            Literal::Integer(i) => match size {
-                1 => (*i as u8).to_ne_bytes().to_vec(),
-                2 => (*i as u16).to_ne_bytes().to_vec(),
-                4 => (*i as u32).to_ne_bytes().to_vec(),
-                _ => i.to_ne_bytes().to_vec(),
-            },
+            Literal::Character(_) if size != 4 => {
+                Err(format!("a character literal cannot be stored in {} bytes", size).into())
+            }
+            Literal::Character(i) => Ok((*i as u32).to_ne_bytes().to_vec()),
+            Literal::String(i) => Ok(i.as_bytes().to_vec()),
         }
     }
 }
+
+fn parse_suffix(value: &str) -> Result<Type, Str> {
+    match value {
+        "u8" => Ok(Type::Uint8),
+        "u16" => Ok(Type::Uint16),
+        "u32" => Ok(Type::Uint32),
+        "u64" => Ok(Type::Uint64),
+        "i8" => Ok(Type::Int8),
+        "i16" => Ok(Type::Int16),
+        "i32" => Ok(Type::Int32),
+        "i64" => Ok(Type::Int64),
+        _ => Err(format!("unknown integer suffix `{}`", value).into()),
+    }
+}
+
+pub fn integer_range(size: usize, signed: bool) -> (i128, i128) {
+    let bits = size as u32 * 8;
+
+    if signed {
+        (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+    } else {
+        (0, (1 << bits) - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> Result<Literal, Str> {
+        Literal::parse_number(value)
+    }
+
+    #[test]
+    fn parses_integers_in_every_radix() {
+        assert_eq!(parse("42"), Ok(Literal::Integer(42, None)));
+        assert_eq!(parse("-3"), Ok(Literal::Integer(-3, None)));
+        assert_eq!(parse("0xFF"), Ok(Literal::Integer(255, None)));
+        assert_eq!(parse("0b1010_0001"), Ok(Literal::Integer(161, None)));
+        assert_eq!(parse("0o17"), Ok(Literal::Integer(15, None)));
+    }
+
+    #[test]
+    fn parses_suffixes() {
+        assert_eq!(parse("255u8"), Ok(Literal::Integer(255, Some(Type::Uint8))));
+        assert_eq!(
+            parse("-128i8"),
+            Ok(Literal::Integer(-128, Some(Type::Int8)))
+        );
+        assert!(parse("256u8").is_err());
+        assert!(parse("1u7").is_err());
+    }
+
+    #[test]
+    fn parses_floats() {
+        assert_eq!(parse("1.5"), Ok(Literal::Float(1.5)));
+        assert_eq!(parse("-2.5"), Ok(Literal::Float(-2.5)));
+        assert_eq!(parse("1e3"), Ok(Literal::Float(1000.0)));
+        assert_eq!(parse("2E-1"), Ok(Literal::Float(0.2)));
+    }
+
+    #[test]
+    fn rejects_integers_wider_than_64_bits() {
+        assert!(parse("18446744073709551615").is_ok());
+        assert!(parse("18446744073709551616").is_err());
+        assert!(parse("340282366920938463463374607431768211456").is_err());
+        assert!(parse("0x1_0000_0000_0000_0000").is_err());
+        assert!(parse("-9223372036854775809").is_err());
+    }
+
+    #[test]
+    fn stores_integers_only_in_integer_sizes() {
+        let literal = Literal::Integer(5, None);
+
+        assert_eq!(literal.as_bytes(2), Ok(5u16.to_ne_bytes().to_vec()));
+        assert!(literal.as_bytes(16).is_err());
+        assert!(literal.as_bytes(3).is_err());
+        assert!(literal.as_bytes(0).is_err());
+    }
+
+    #[test]
+    fn checks_literals_against_the_type_they_are_stored_in() {
+        assert!(Literal::Integer(-1, None).check_type(&Type::Uint8).is_err());
+        assert!(Literal::Integer(200, None).check_type(&Type::Int8).is_err());
+        assert!(Literal::Integer(255, None).check_type(&Type::Uint8).is_ok());
+        assert!(Literal::Integer(-128, None).check_type(&Type::Int8).is_ok());
+        assert!(Literal::Integer(1, Some(Type::Uint8))
+            .check_type(&Type::Int8)
+            .is_err());
+        assert!(Literal::Integer(1, None)
+            .check_type(&Type::Float64)
+            .is_err());
+        assert!(Literal::Float(1.0).check_type(&Type::Int32).is_err());
+        assert!(Literal::Character('a').check_type(&Type::Uint32).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_numbers() {
+        assert!(parse("0x").is_err());
+        assert!(parse("0b102").is_err());
+        assert!(parse("12ab").is_err());
+        assert!(parse("inf").is_err());
+    }
+}
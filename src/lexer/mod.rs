@@ -1,3 +1,4 @@
+use crate::alias::Str;
 use crate::error::JAPLError;
 
 mod token;
@@ -7,26 +8,95 @@ mod keyword;
 pub use keyword::Keyword;
 
 mod literal;
-pub use literal::Literal;
+pub use literal::{integer_range, Literal};
 
 mod symbol;
 pub use symbol::Symbol;
 
-pub fn tokenise(file: &str) -> Result<Vec<Token>, JAPLError> {
+mod span;
+pub use span::Span;
+
+mod cursor;
+use cursor::Cursor;
+
+pub fn tokenise(file: &str) -> Result<Vec<(Token, Span)>, Vec<JAPLError>> {
     let mut tokens = Vec::new();
+    let mut errors = Vec::new();
     let mut builder = String::new();
 
-    let mut char_iter = file.chars().peekable();
-    while let Some(cur_char) = char_iter.next() {
+    // position of the first character in `builder`
+    let mut start = Span::default();
+
+    let mut cursor = Cursor::new(file);
+    loop {
+        let position = cursor.position();
+        let Some(cur_char) = cursor.bump() else {
+            break;
+        };
+
         if cur_char.is_whitespace() {
             if !builder.is_empty() {
-                tokens.push(builder.as_str().try_into()?);
+                push_token(&builder, start, &mut tokens, &mut errors);
+                builder.clear();
+            }
+
+            continue;
+        }
+
+        if cur_char == '/' && matches!(cursor.peek(), Some('/') | Some('*')) {
+            if !builder.is_empty() {
+                push_token(&builder, start, &mut tokens, &mut errors);
+                builder.clear();
+            }
+
+            match comment(file, position, &mut cursor) {
+                Ok(i) => tokens.push(i),
+                Err(i) => errors.push(i),
+            }
+
+            continue;
+        }
+
+        if cur_char == '"' || cur_char == '\'' {
+            if !builder.is_empty() {
+                push_token(&builder, start, &mut tokens, &mut errors);
+                builder.clear();
+            }
+
+            match quoted(cur_char, position, &mut cursor) {
+                Ok(i) => tokens.push(i),
+                Err(i) => errors.push(i),
+            }
+
+            continue;
+        }
+
+        // a digit continues a word such as `a0` or `int32`, anywhere else it starts a
+        // number, even right after a symbol as in `[0x2]`
+        let in_word = builder
+            .chars()
+            .last()
+            .is_some_and(|i| i.is_alphanumeric() || i == '_');
+
+        let negative_number = cur_char == '-' && cursor.peek().is_some_and(|i| i.is_ascii_digit());
+        if !in_word && (cur_char.is_ascii_digit() || negative_number) {
+            if !builder.is_empty() {
+                push_token(&builder, start, &mut tokens, &mut errors);
                 builder.clear();
             }
 
+            match number(file, position, &mut cursor) {
+                Ok(i) => tokens.push(i),
+                Err(i) => errors.push(i),
+            }
+
             continue;
         }
 
+        if builder.is_empty() {
+            start = position;
+        }
+
         builder.push(cur_char);
 
         if Token::try_from(builder.as_str()).is_err() {
@@ -35,15 +105,238 @@ pub fn tokenise(file: &str) -> Result<Vec<Token>, JAPLError> {
             }
 
             builder.pop();
-            tokens.push(builder.as_str().try_into()?);
+            push_token(&builder, start, &mut tokens, &mut errors);
             builder.clear();
             builder.push(cur_char);
+            start = position;
         }
     }
 
     if !builder.is_empty() {
-        tokens.push(builder.as_str().try_into()?);
+        push_token(&builder, start, &mut tokens, &mut errors);
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
     }
 
     Ok(tokens)
 }
+
+fn push_token(
+    builder: &str,
+    start: Span,
+    tokens: &mut Vec<(Token, Span)>,
+    errors: &mut Vec<JAPLError>,
+) {
+    let span = Span::new(
+        start.line,
+        start.column,
+        start.start,
+        start.start + builder.len(),
+    );
+
+    match Token::try_from(builder) {
+        Ok(tkn) => tokens.push((tkn, span)),
+        Err(_) => errors.push(JAPLError::InvalidIdentifier(builder.into(), span)),
+    }
+}
+
+// `//` comments run to the end of the line, `/* */` comments may be nested
+fn comment(file: &str, start: Span, cursor: &mut Cursor) -> Result<(Token, Span), JAPLError> {
+    if cursor.bump() == Some('/') {
+        while !matches!(cursor.peek(), None | Some('\n')) {
+            cursor.bump();
+        }
+    } else {
+        let mut depth = 1;
+
+        while depth > 0 {
+            match cursor.bump() {
+                Some('/') if cursor.peek() == Some('*') => {
+                    cursor.bump();
+                    depth += 1;
+                }
+                Some('*') if cursor.peek() == Some('/') => {
+                    cursor.bump();
+                    depth -= 1;
+                }
+                Some(_) => {}
+                None => {
+                    return Err(JAPLError::UnterminatedComment(Span::new(
+                        start.line,
+                        start.column,
+                        start.start,
+                        start.start + 2,
+                    )))
+                }
+            }
+        }
+    }
+
+    let end = cursor.position().start;
+    let span = Span::new(start.line, start.column, start.start, end);
+
+    Ok((Token::Comment(file[start.start..end].into()), span))
+}
+
+// scans a numeric literal; its first character has already been consumed
+fn number(file: &str, start: Span, cursor: &mut Cursor) -> Result<(Token, Span), JAPLError> {
+    let mut last = file[start.start..].chars().next();
+
+    while let Some(i) = cursor.peek() {
+        // the sign of an exponent, as in `2e-1`, belongs to a decimal literal
+        let exponent_sign = matches!(i, '+' | '-')
+            && matches!(last, Some('e' | 'E'))
+            && is_decimal(file, start, cursor);
+
+        if !(i.is_alphanumeric() || i == '_' || i == '.' || exponent_sign) {
+            break;
+        }
+
+        last = cursor.bump();
+    }
+
+    let end = cursor.position().start;
+    let span = Span::new(start.line, start.column, start.start, end);
+
+    Literal::parse_number(&file[start.start..end])
+        .map(|i| (Token::Literal(i), span))
+        .map_err(|e| JAPLError::InvalidLiteral(e, span))
+}
+
+fn is_decimal(file: &str, start: Span, cursor: &mut Cursor) -> bool {
+    let text = &file[start.start..cursor.position().start];
+    let text = text.strip_prefix('-').unwrap_or(text);
+
+    !matches!(text.get(..2), Some("0x" | "0X" | "0b" | "0B" | "0o" | "0O"))
+}
+
+// scans a character or string literal; the opening quote has already been consumed
+fn quoted(quote: char, start: Span, cursor: &mut Cursor) -> Result<(Token, Span), JAPLError> {
+    let mut value = String::new();
+    let mut error = None;
+
+    loop {
+        let position = cursor.position();
+
+        match cursor.bump() {
+            Some(i) if i == quote => break,
+            Some('\\') => match escape(cursor) {
+                Ok(i) => value.push(i),
+                Err(e) => {
+                    let end = cursor.position().start;
+                    let span = Span::new(position.line, position.column, position.start, end);
+                    error.get_or_insert(JAPLError::InvalidLiteral(e, span));
+                }
+            },
+            Some('\n') | None => {
+                return Err(JAPLError::InvalidLiteral(
+                    "missing closing quote".into(),
+                    Span::new(start.line, start.column, start.start, start.start + 1),
+                ))
+            }
+            Some(i) => value.push(i),
+        }
+    }
+
+    let span = Span::new(
+        start.line,
+        start.column,
+        start.start,
+        cursor.position().start,
+    );
+
+    if let Some(e) = error {
+        return Err(e);
+    }
+
+    if quote == '"' {
+        return Ok((Token::Literal(Literal::String(value.into())), span));
+    }
+
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(i), None) => Ok((Token::Literal(Literal::Character(i)), span)),
+        _ => Err(JAPLError::InvalidLiteral(
+            "character literals must contain exactly one character".into(),
+            span,
+        )),
+    }
+}
+
+// decodes the escape sequence following a `\`
+fn escape(cursor: &mut Cursor) -> Result<char, Str> {
+    match cursor.bump() {
+        Some('n') => Ok('\n'),
+        Some('t') => Ok('\t'),
+        Some('r') => Ok('\r'),
+        Some('0') => Ok('\0'),
+        Some('\\') => Ok('\\'),
+        Some('\'') => Ok('\''),
+        Some('"') => Ok('"'),
+        Some('u') => {
+            if cursor.bump() != Some('{') {
+                return Err("expected `{` after `\\u`".into());
+            }
+
+            let mut digits = String::new();
+            loop {
+                match cursor.peek() {
+                    Some('}') => {
+                        cursor.bump();
+                        break;
+                    }
+                    Some(i) if i.is_ascii_hexdigit() && digits.len() < 6 => {
+                        digits.push(i);
+                        cursor.bump();
+                    }
+                    _ => return Err("malformed unicode escape".into()),
+                }
+            }
+
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("invalid unicode escape: \\u{{{}}}", digits).into())
+        }
+        Some(i) => Err(format!("unknown escape sequence: \\{}", i).into()),
+        None => Err("unterminated escape sequence".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literals(file: &str) -> Vec<Literal> {
+        tokenise(file)
+            .unwrap()
+            .into_iter()
+            .filter_map(|(i, _)| match i {
+                Token::Literal(i) => Some(i),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scans_signed_exponents() {
+        assert_eq!(
+            literals("load 2e-1 g0\nload -1.5E+2 g1"),
+            vec![Literal::Float(0.2), Literal::Float(-150.0)]
+        );
+    }
+
+    #[test]
+    fn leaves_signs_after_other_numbers_alone() {
+        assert_eq!(
+            literals("load 0x1e-1 c0"),
+            vec![Literal::Integer(30, None), Literal::Integer(-1, None)]
+        );
+        assert_eq!(
+            literals("load 2-1 c0"),
+            vec![Literal::Integer(2, None), Literal::Integer(-1, None)]
+        );
+    }
+}
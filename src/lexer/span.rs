@@ -0,0 +1,18 @@
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(line: usize, column: usize, start: usize, end: usize) -> Self {
+        Self {
+            line,
+            column,
+            start,
+            end,
+        }
+    }
+}
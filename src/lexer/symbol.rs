@@ -1,6 +1,4 @@
-use crate::error::JAPLError;
-
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Symbol {
     Colon,
     Semicolon,
@@ -15,7 +13,7 @@ pub enum Symbol {
 }
 
 impl TryFrom<&str> for Symbol {
-    type Error = JAPLError;
+    type Error = ();
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
@@ -29,7 +27,7 @@ impl TryFrom<&str> for Symbol {
             "]" => Ok(Self::SquareClose),
             "{" => Ok(Self::CurlyOpen),
             "}" => Ok(Self::CurlyClose),
-            _ => Err(JAPLError::InvalidIdentifier(value.into())),
+            _ => Err(()),
         }
     }
 }
@@ -1,20 +1,20 @@
 use crate::alias::Str;
-use crate::error::JAPLError;
 
 use super::keyword::Keyword;
 use super::literal::Literal;
 use super::symbol::Symbol;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     Keyword(Keyword),
     Symbol(Symbol),
     Identifier(Str),
     Literal(Literal),
+    Comment(Str),
 }
 
 impl TryFrom<&str> for Token {
-    type Error = JAPLError;
+    type Error = ();
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         if let Ok(i) = Keyword::try_from(value) {
@@ -33,7 +33,7 @@ impl TryFrom<&str> for Token {
             return Ok(Self::Identifier(value.into()));
         }
 
-        Err(JAPLError::InvalidIdentifier(value.into()))
+        Err(())
     }
 }
 
@@ -0,0 +1,37 @@
+//! JAPL, a small register based assembly language.
+//!
+//! ```
+//! let program = japl::compile("push int32 x\nload 40 c0\nadd c0 2 c0\nunload c0 x").unwrap();
+//!
+//! let mut vm = japl::Vm::new(program).unwrap();
+//! vm.run().unwrap();
+//!
+//! assert_eq!(vm.get::<i32>("x"), Ok(42));
+//! ```
+
+mod alias;
+pub mod bytecode;
+pub mod error;
+pub mod lexer;
+pub mod parser;
+pub mod runtime;
+
+pub use error::{BytecodeError, Diagnostics, LinkError, MissingFunction, RuntimeError, Stage};
+pub use runtime::{Host, Program, State, Vm};
+
+pub fn compile(source: &str) -> Result<Program, Diagnostics> {
+    let tokens = lexer::tokenise(source).map_err(|errors| Diagnostics {
+        stage: Stage::Lexer,
+        errors,
+    })?;
+
+    parser::parse(tokens).map_err(|errors| Diagnostics {
+        stage: Stage::Parser,
+        errors,
+    })
+}
+
+// compiles and runs a program that calls no host functions, returning its exit status
+pub fn run(source: &str) -> Result<i32, Box<dyn std::error::Error>> {
+    Ok(Vm::new(compile(source)?)?.run()?)
+}
@@ -1,34 +1,184 @@
-mod alias;
-mod error;
-mod lexer;
-mod parser;
-mod runtime;
+use std::io::Write;
+
+use japl::runtime::DEFAULT_MEMORY_LIMIT;
+use japl::{bytecode, Program, Stage, Vm};
+
+// exit codes for failures before and during execution, a program that
+// runs to completion exits with its own status instead
+const EXIT_USAGE: i32 = 64;
+const EXIT_LEXER: i32 = 65;
+const EXIT_PARSER: i32 = 66;
+const EXIT_LINK: i32 = 67;
+const EXIT_BYTECODE: i32 = 68;
+const EXIT_RUNTIME: i32 = 70;
 
 fn main() {
-    let mut args = std::env::args();
-    args.next();
+    let mut args = std::env::args().skip(1).peekable();
+
+    match args.peek().map(String::as_str) {
+        Some("build") => {
+            args.next();
+            build(args);
+        }
+        Some("run") => {
+            args.next();
+            run(args);
+        }
+        _ => run(args),
+    }
+}
+
+// japl build <file> [-o <out>]
+fn build(mut args: impl Iterator<Item = String>) {
+    let mut file = None;
+    let mut out = None;
+
+    while let Some(arg) = args.next() {
+        if arg == "-o" {
+            out = match args.next() {
+                Some(i) => Some(i),
+                None => {
+                    eprintln!("error: -o expects an output file");
+                    std::process::exit(EXIT_USAGE);
+                }
+            };
+        } else {
+            file = Some(arg);
+        }
+    }
+
+    let Some(file) = file else {
+        eprintln!("usage: japl build <file> [-o <out>]");
+        std::process::exit(EXIT_USAGE);
+    };
 
-    let file = args.next().unwrap_or("program.japl".into());
-    let program = std::fs::read_to_string(file).unwrap();
+    let out = out.unwrap_or_else(|| {
+        std::path::Path::new(&file)
+            .with_extension("japlc")
+            .to_string_lossy()
+            .into_owned()
+    });
 
-    let tokens = match lexer::tokenise(program.trim()) {
+    let bytes = read(&file);
+    let source = match String::from_utf8(bytes) {
         Ok(i) => i,
-        Err(i) => {
-            eprintln!("Error: {:?}", i);
-            return;
+        Err(_) => {
+            eprintln!("error: {} is not valid UTF-8", file);
+            std::process::exit(EXIT_USAGE);
         }
     };
 
-    let (instruction_set, labels) = match parser::parse(tokens) {
+    let program = compile(&file, &source);
+
+    if let Err(e) = std::fs::write(&out, bytecode::encode(&program)) {
+        eprintln!("error: cannot write {}: {}", out, e);
+        std::process::exit(EXIT_USAGE);
+    }
+}
+
+// japl [run] [--memory-limit <bytes>] [--dump] <file>, where the file is source or bytecode
+fn run(mut args: impl Iterator<Item = String>) {
+    let mut file = None;
+    let mut memory_limit = DEFAULT_MEMORY_LIMIT;
+    let mut dump = false;
+
+    while let Some(arg) = args.next() {
+        if arg == "--memory-limit" {
+            memory_limit = match args.next().map(|i| i.parse()) {
+                Some(Ok(i)) => i,
+                _ => {
+                    eprintln!("error: --memory-limit expects a size in bytes");
+                    std::process::exit(EXIT_USAGE);
+                }
+            };
+        } else if arg == "--dump" {
+            dump = true;
+        } else {
+            file = Some(arg);
+        }
+    }
+
+    let file = file.unwrap_or("program.japl".into());
+    let bytes = read(&file);
+
+    // compiled programs skip the lexer and parser entirely
+    let program = if bytecode::is_bytecode(&bytes) {
+        match bytecode::decode(&bytes) {
+            Ok(i) => i,
+            Err(e) => {
+                eprintln!("error: {}: {}", file, e);
+                std::process::exit(EXIT_BYTECODE);
+            }
+        }
+    } else {
+        match String::from_utf8(bytes) {
+            Ok(source) => compile(&file, &source),
+            Err(_) => {
+                eprintln!("error: {} is neither source nor bytecode", file);
+                std::process::exit(EXIT_USAGE);
+            }
+        }
+    };
+
+    let mut vm = match Vm::new(program) {
+        Ok(i) => i,
+        Err(e) => {
+            for i in e.missing {
+                eprintln!("error: {}", i);
+            }
+
+            std::process::exit(EXIT_LINK);
+        }
+    };
+
+    vm.set_memory_limit(memory_limit);
+    let status = match vm.run() {
         Ok(i) => {
-            // println!("{:#?}", i);
+            for leak in vm.leaks() {
+                eprintln!(
+                    "warning: {} bytes at address {} allocated at pc {} were never freed",
+                    leak.size, leak.address, leak.site
+                );
+            }
+
             i
         }
         Err(i) => {
-            eprintln!("Error: {:?}", i);
-            return;
+            eprintln!("error: {}", i);
+            EXIT_RUNTIME
         }
     };
 
-    runtime::run(instruction_set, labels);
+    if dump {
+        println!("stack: {:?}", vm.memory());
+        println!("register: {:#?}", vm.register());
+    }
+
+    // `exit` skips destructors, so anything printed without a newline would be lost
+    let _ = std::io::stdout().flush();
+    std::process::exit(status);
+}
+
+fn read(file: &str) -> Vec<u8> {
+    match std::fs::read(file) {
+        Ok(i) => i,
+        Err(e) => {
+            eprintln!("error: cannot read {}: {}", file, e);
+            std::process::exit(EXIT_USAGE);
+        }
+    }
+}
+
+fn compile(file: &str, source: &str) -> Program {
+    match japl::compile(source) {
+        Ok(i) => i,
+        Err(diagnostics) => {
+            eprintln!("{}\n", diagnostics.render(file, source));
+
+            std::process::exit(match diagnostics.stage {
+                Stage::Lexer => EXIT_LEXER,
+                Stage::Parser => EXIT_PARSER,
+            });
+        }
+    }
 }
@@ -1,64 +1,344 @@
-use crate::alias::{Name, Str};
+use crate::alias::Str;
 use crate::error::JAPLError;
-use crate::lexer::Token;
-use crate::runtime::{RegisterName, Type};
-
-pub fn get_register_name(tkn: Option<Token>) -> Result<RegisterName, JAPLError> {
-    tkn.ok_or(JAPLError::InvalidArgument(
-        "Missing token: RegisterName".into(),
-    ))
-    .map(|tkn| {
-        let err = JAPLError::InvalidArgument("Exptected keyword: Register Name".into());
-
-        if let Token::Keyword(kw) = tkn {
-            RegisterName::try_from(kw).map_err(|_| err)
-        } else {
-            Err(err)
+use crate::lexer::{Keyword, Literal, Span, Symbol, Token};
+use crate::runtime::{
+    Label, Operand, RegisterClass, RegisterName, Type, Value, Variable, MAX_TYPE_SIZE,
+};
+
+use super::stream::TokenStream;
+use super::Structs;
+
+pub fn get_register_name(tokens: &mut TokenStream) -> Result<RegisterName, JAPLError> {
+    tokens
+        .next_argument()
+        .ok_or(JAPLError::InvalidArgument(
+            "Missing token: Register Name".into(),
+            tokens.last_span(),
+        ))
+        .map(|(tkn, span)| {
+            let err = JAPLError::InvalidArgument("Expected keyword: Register Name".into(), span);
+
+            if let Token::Keyword(kw) = tkn {
+                RegisterName::try_from(kw).map_err(|_| err)
+            } else {
+                Err(err)
+            }
+        })?
+}
+
+// addresses are 64 bits wide, so only d registers can hold them
+pub fn get_pointer_register(tokens: &mut TokenStream) -> Result<RegisterName, JAPLError> {
+    let reg = get_register_name(tokens)?;
+
+    if reg.register_class() != RegisterClass::D {
+        return Err(JAPLError::InvalidArgument(
+            "Addresses can only be held in a d register".into(),
+            tokens.last_span(),
+        ));
+    }
+
+    Ok(reg)
+}
+
+// the register of `sprint`, `sread`, `printc` or `readc`, and the type its contents are taken as
+pub fn get_typed_register(
+    tokens: &mut TokenStream,
+    kw: Keyword,
+) -> Result<(RegisterName, Type), JAPLError> {
+    let reg = get_register_name(tokens)?;
+    let class = reg.register_class();
+
+    let value_type = match kw {
+        Keyword::PrintSigned | Keyword::ReadSigned => {
+            class.signed_type().ok_or(JAPLError::InvalidArgument(
+                "Signed values can only be held in an integer register".into(),
+                tokens.last_span(),
+            ))?
+        }
+        _ if class == RegisterClass::C => Type::Character,
+        _ => {
+            return Err(JAPLError::InvalidArgument(
+                "Characters can only be held in a c register".into(),
+                tokens.last_span(),
+            ))
         }
-    })?
+    };
+
+    Ok((reg, value_type))
+}
+
+// a source operand before it is known which register class an immediate belongs to
+pub enum Source {
+    Register(RegisterName),
+    Literal(Literal, Span),
 }
 
-pub fn get_variable_type(tkn: Option<Token>) -> Result<Type, JAPLError> {
-    tkn.ok_or(JAPLError::InvalidArgument(
-        "Missing token: Variable Type".into(),
-    ))
-    .map(|tkn| {
-        let err = JAPLError::InvalidArgument("Exptected keyword: Variable Type".into());
+pub fn get_source(tokens: &mut TokenStream) -> Result<Source, JAPLError> {
+    tokens
+        .next_argument()
+        .ok_or(JAPLError::InvalidArgument(
+            "Missing token: Register Name or Literal".into(),
+            tokens.last_span(),
+        ))
+        .map(|(tkn, span)| {
+            let err =
+                JAPLError::InvalidArgument("Expected token: Register Name or Literal".into(), span);
 
-        if let Token::Keyword(kw) = tkn {
-            Type::try_from(kw).map_err(|_| err)
-        } else {
-            Err(err)
+            match tkn {
+                Token::Keyword(kw) => RegisterName::try_from(kw)
+                    .map(Source::Register)
+                    .map_err(|_| err),
+                Token::Literal(literal) => Ok(Source::Literal(literal, span)),
+                _ => Err(err),
+            }
+        })?
+}
+
+// a built in type keyword or the name of a struct declared earlier, either
+// of which can be followed by `[length]` to make an array of them
+pub fn get_variable_type(tokens: &mut TokenStream, structs: &Structs) -> Result<Type, JAPLError> {
+    let var_type = get_element_type(tokens, structs)?;
+
+    if !matches!(tokens.peek(), Some((Token::Symbol(Symbol::SquareOpen), _))) {
+        return Ok(var_type);
+    }
+
+    // an element is only ever moved through a register, and no register holds a whole struct
+    if let Type::Struct(record) = &var_type {
+        return Err(JAPLError::InvalidArgument(
+            format!("Arrays of struct {} are not supported", record.name).into(),
+            tokens.last_span(),
+        ));
+    }
+
+    tokens.next();
+
+    let len = match tokens.next_argument() {
+        Some((Token::Literal(Literal::Integer(i, _)), span)) if i > 0 => {
+            let size = usize::try_from(i)
+                .ok()
+                .and_then(|len| var_type.size().checked_mul(len))
+                .filter(|size| *size <= MAX_TYPE_SIZE);
+
+            if size.is_none() {
+                return Err(JAPLError::InvalidArgument(
+                    format!("Array of {} elements is too large", i).into(),
+                    span,
+                ));
+            }
+
+            i as usize
+        }
+        Some((_, span)) => {
+            return Err(JAPLError::InvalidArgument(
+                "Array lengths must be a positive integer literal".into(),
+                span,
+            ))
         }
-    })?
+        None => {
+            return Err(JAPLError::InvalidArgument(
+                "Missing token: Array Length".into(),
+                tokens.last_span(),
+            ))
+        }
+    };
+
+    get_symbol(tokens, Symbol::SquareClose, "]")?;
+    Ok(Type::Array(Box::new(var_type), len))
+}
+
+fn get_element_type(tokens: &mut TokenStream, structs: &Structs) -> Result<Type, JAPLError> {
+    tokens
+        .next_argument()
+        .ok_or(JAPLError::InvalidArgument(
+            "Missing token: Variable Type".into(),
+            tokens.last_span(),
+        ))
+        .map(|(tkn, span)| match tkn {
+            Token::Keyword(kw) => Type::try_from(kw).map_err(|_| {
+                JAPLError::InvalidArgument("Expected keyword: Variable Type".into(), span)
+            }),
+            Token::Identifier(ident) => structs.get(&ident).map(|i| Type::Struct(i.clone())).ok_or(
+                JAPLError::InvalidArgument(format!("Unknown struct: {}", ident).into(), span),
+            ),
+            _ => Err(JAPLError::InvalidArgument(
+                "Expected token: Variable Type".into(),
+                span,
+            )),
+        })?
+}
+
+pub fn get_variable(tokens: &mut TokenStream) -> Result<Variable, JAPLError> {
+    let (name, span) = get_name(tokens, "Variable Name")?;
+    let mut var = Variable::Name(name.into(), Vec::new(), span);
+
+    get_fields(tokens, &mut var)?;
+    Ok(var)
+}
+
+pub fn get_label_name(tokens: &mut TokenStream) -> Result<Str, JAPLError> {
+    get_name(tokens, "Label Name").map(|(name, _)| name)
+}
+
+pub fn get_label(tokens: &mut TokenStream) -> Result<Label, JAPLError> {
+    let name = get_label_name(tokens)?;
+    Ok(Label::Name(name, tokens.last_span()))
+}
+
+pub fn get_name(tokens: &mut TokenStream, kind: &str) -> Result<(Str, Span), JAPLError> {
+    tokens
+        .next_argument()
+        .ok_or(JAPLError::InvalidArgument(
+            format!("Missing token: {}", kind).into(),
+            tokens.last_span(),
+        ))
+        .map(|(tkn, span)| {
+            if let Token::Identifier(ident) = tkn {
+                Ok((ident, span))
+            } else {
+                Err(JAPLError::InvalidArgument(
+                    format!("Expected identifier: {}", kind).into(),
+                    span,
+                ))
+            }
+        })?
 }
 
-pub fn get_ident_name(tkn: Option<Token>) -> Result<Name, JAPLError> {
-    tkn.ok_or(JAPLError::InvalidArgument(
-        "Missing token: Identifier".into(),
-    ))
-    .map(|tkn| {
-        let err = JAPLError::InvalidArgument("Exptected identifier: Variable Name".into());
+pub fn get_value(tokens: &mut TokenStream) -> Result<Value, JAPLError> {
+    let mut value = tokens
+        .next_argument()
+        .ok_or(JAPLError::InvalidArgument(
+            "Missing token: Value".into(),
+            tokens.last_span(),
+        ))
+        .map(|(tkn, span)| {
+            Value::try_from((tkn, span))
+                .map_err(|_| JAPLError::InvalidArgument("Expected token: Value".into(), span))
+        })??;
+
+    if let Value::LValue(var) = &mut value {
+        get_fields(tokens, var)?;
+    }
+
+    Ok(value)
+}
 
-        if let Token::Identifier(ident) = tkn {
-            Ok(Name::from(ident))
-        } else {
-            Err(err)
+// the `[index]` following an array variable, the index is an integer register or a literal
+pub fn get_index(tokens: &mut TokenStream) -> Result<Operand, JAPLError> {
+    get_symbol(tokens, Symbol::SquareOpen, "[")?;
+
+    let index = match get_source(tokens)? {
+        Source::Register(i) if i.register_class().is_integer() => Operand::Register(i),
+        Source::Literal(Literal::Integer(i, _), _) if (0..=u64::MAX as i128).contains(&i) => {
+            Operand::Immediate(i as u64)
+        }
+        _ => {
+            return Err(JAPLError::InvalidArgument(
+                "Array indices must be an integer register or a non-negative integer".into(),
+                tokens.last_span(),
+            ))
         }
-    })?
+    };
+
+    get_symbol(tokens, Symbol::SquareClose, "]")?;
+    Ok(index)
 }
 
-pub fn get_label_name(tkn: Option<Token>) -> Result<Str, JAPLError> {
-    tkn.ok_or(JAPLError::InvalidArgument(
-        "Missing token: Label Name".into(),
-    ))
-    .map(|tkn| {
-        let err = JAPLError::InvalidArgument("Exptected identifier: Label Name".into());
+fn get_symbol(tokens: &mut TokenStream, symbol: Symbol, text: &str) -> Result<(), JAPLError> {
+    match tokens.next_argument() {
+        Some((Token::Symbol(i), _)) if i == symbol => Ok(()),
+        Some((_, span)) => Err(JAPLError::InvalidArgument(
+            format!("Expected symbol: {}", text).into(),
+            span,
+        )),
+        None => Err(JAPLError::InvalidArgument(
+            format!("Missing symbol: {}", text).into(),
+            tokens.last_span(),
+        )),
+    }
+}
+
+// the `.field` accesses following a variable name, the span grows to cover them
+fn get_fields(tokens: &mut TokenStream, var: &mut Variable) -> Result<(), JAPLError> {
+    let Variable::Name(_, fields, span) = var else {
+        return Ok(());
+    };
+
+    while let Some((Token::Symbol(Symbol::Dot), _)) = tokens.peek() {
+        tokens.next();
+
+        let (field, field_span) = get_name(tokens, "Field Name")?;
+        fields.push(field);
+        span.end = field_span.end;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::{Instruction, RegisterName, Type};
 
-        if let Token::Identifier(ident) = tkn {
-            Ok(ident)
-        } else {
-            Err(err)
+    fn errors(source: &str) -> Vec<String> {
+        match crate::compile(source) {
+            Ok(_) => Vec::new(),
+            Err(e) => e.errors.iter().map(|i| i.to_string()).collect(),
         }
-    })?
+    }
+
+    #[test]
+    fn prints_and_reads_registers_as_their_signed_or_character_type() {
+        let program = crate::compile("sprint c0\nsread a1\nprintc c2\nreadc c3").unwrap();
+
+        assert!(matches!(
+            program.instructions[..],
+            [
+                Instruction::PrintRegister(RegisterName::C0, Type::Int32),
+                Instruction::ReadRegister(RegisterName::A1, Type::Int8),
+                Instruction::PrintRegister(RegisterName::C2, Type::Character),
+                Instruction::ReadRegister(RegisterName::C3, Type::Character),
+            ]
+        ));
+
+        assert_eq!(errors("sprint f0").len(), 1);
+        assert_eq!(errors("sread i0").len(), 1);
+        assert_eq!(errors("printc d0").len(), 1);
+        assert_eq!(errors("readc a0").len(), 1);
+    }
+
+    #[test]
+    fn rejects_moves_and_conversions_between_the_wrong_classes() {
+        assert_eq!(
+            errors("zext d0 c0"),
+            ["Invalid arguments passed: ZeroExtend cannot convert D0 to C0"]
+        );
+        assert_eq!(errors("itof c0 d0").len(), 1);
+        assert_eq!(errors("ftoi g0 f0").len(), 1);
+        assert_eq!(errors("move c0 d0").len(), 1);
+        assert!(errors("zext c0 d0\nitof c0 g0\nftosi f0 a0\ntrunc d0 a0\nmove f0 f1").is_empty());
+    }
+
+    #[test]
+    fn rejects_arrays_of_structs() {
+        assert_eq!(
+            errors("struct Point\nint32 x\nint32 y\nend\npush Point[3] pts"),
+            ["Invalid arguments passed: Arrays of struct Point are not supported"]
+        );
+    }
+
+    #[test]
+    fn rejects_arrays_that_are_too_large() {
+        assert_eq!(
+            errors("push int64[4294967296] xs"),
+            ["Invalid arguments passed: Array of 4294967296 elements is too large"]
+        );
+        assert!(errors("push int8[4294967295] xs").is_empty());
+    }
+
+    #[test]
+    fn rejects_array_lengths_that_are_not_positive() {
+        assert_eq!(errors("push int32[0] xs").len(), 1);
+        assert_eq!(errors("push int32[-1] xs").len(), 1);
+        assert_eq!(errors("push int32[c0] xs").len(), 1);
+    }
 }
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use crate::alias::Str;
+use crate::error::JAPLError;
+use crate::lexer::Span;
+use crate::runtime::{Instruction, Label, Program};
+
+// checks every label definition and replaces each label name with the index it labels
+pub fn link(program: &mut Program, labels: Vec<(Str, usize, Span)>) -> Result<(), Vec<JAPLError>> {
+    let mut errors = Vec::new();
+    let mut spans: HashMap<Str, Span> = HashMap::new();
+
+    for (name, target, span) in labels {
+        if let Some(first) = spans.get(&name) {
+            errors.push(JAPLError::DuplicateLabel(name, span, *first));
+            continue;
+        }
+
+        spans.insert(name.clone(), span);
+        program.labels.insert(name, target);
+    }
+
+    let owners = owners(&program.instructions);
+
+    for pc in 0..program.instructions.len() {
+        let Some(Label::Name(name, span)) = label_mut(&mut program.instructions[pc]) else {
+            continue;
+        };
+
+        let (name, span) = (name.clone(), *span);
+
+        let Some(target) = program.labels.get(&name).copied() else {
+            errors.push(JAPLError::UndefinedLabel(name, span));
+            continue;
+        };
+
+        // a call has to enter a function so that it gets its own frame, and a jump has to
+        // stay in the function it is in, since locals are only valid in their own frame
+        match &program.instructions[pc] {
+            Instruction::Call(_) | Instruction::CallIf(..) => {
+                if !matches!(
+                    program.instructions.get(target),
+                    Some(Instruction::Function(..))
+                ) {
+                    errors.push(JAPLError::CallToNonFunction(name, span));
+                    continue;
+                }
+            }
+            _ => {
+                // a label after the last instruction is outside every function
+                if owners.get(target).copied().flatten() != owners[pc] {
+                    errors.push(JAPLError::CrossFunctionJump(name, span));
+                    continue;
+                }
+            }
+        }
+
+        if let Some(label) = label_mut(&mut program.instructions[pc]) {
+            *label = Label::Target(target);
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(())
+}
+
+fn label_mut(instruction: &mut Instruction) -> Option<&mut Label> {
+    match instruction {
+        Instruction::Call(label)
+        | Instruction::CallIf(label, _)
+        | Instruction::Jump(label)
+        | Instruction::JumpIf(label, _)
+        | Instruction::JumpFlag(label, _) => Some(label),
+        _ => None,
+    }
+}
+
+// the `fn` each instruction is in, the `fn` itself counts as code outside the body
+pub fn owners(instructions: &[Instruction]) -> Vec<Option<usize>> {
+    let mut owners = vec![None; instructions.len()];
+
+    for (start, instruction) in instructions.iter().enumerate() {
+        if let Instruction::Function(body_end, _) = instruction {
+            for owner in owners.iter_mut().take(body_end + 1).skip(start + 1) {
+                *owner = Some(start);
+            }
+        }
+    }
+
+    owners
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(source: &str) -> Vec<String> {
+        match crate::compile(source) {
+            Ok(_) => Vec::new(),
+            Err(e) => e.errors.iter().map(|i| i.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn links_labels_to_the_instruction_after_them() {
+        let program = crate::compile("jump skip\nhalt\nskip:\nhalt").unwrap();
+
+        assert_eq!(program.labels["skip"], 2);
+        assert!(matches!(
+            program.instructions[0],
+            Instruction::Jump(Label::Target(2))
+        ));
+    }
+
+    #[test]
+    fn rejects_duplicate_and_undefined_labels() {
+        assert_eq!(
+            errors("top:\nhalt\ntop:\njump missing"),
+            [
+                "Duplicate label: top, first defined at 1:1",
+                "Undefined label: missing"
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_jumps_across_function_bodies() {
+        assert_eq!(
+            errors("fn body\ninside:\nend\njump inside"),
+            ["Cannot jump to label inside across a function boundary"]
+        );
+        assert_eq!(
+            errors("outside:\nfn body\njump outside\nend"),
+            ["Cannot jump to label outside across a function boundary"]
+        );
+        assert!(errors("fn body\nagain:\njump again\nend\ndone:\njump done").is_empty());
+    }
+
+    #[test]
+    fn rejects_calls_to_plain_labels() {
+        assert_eq!(
+            errors("plain:\ncall plain"),
+            ["Cannot call label plain, it is not a fn"]
+        );
+        assert!(errors("fn body\nend\ncall body").is_empty());
+    }
+}
@@ -1,191 +1,666 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
-use crate::alias::Str;
+use crate::alias::{Name, Str};
 use crate::error::JAPLError;
-use crate::lexer::{Keyword, Symbol, Token};
-use crate::runtime::{BinOperator, Instruction, UnOperator, Value};
+use crate::lexer::{Keyword, Literal, Span, Symbol, Token};
+use crate::runtime::{
+    BinOperator, Conversion, Element, Flag, Instruction, Operand, Program, RegisterClass,
+    RegisterName, Struct, Type, UnOperator, Value, Variable, MAX_TYPE_SIZE,
+};
 
 mod convert;
+use convert::Source;
 
-pub fn parse(tokens: Vec<Token>) -> Result<(Vec<Instruction>, HashMap<Str, usize>), JAPLError> {
-    let mut token_iter = tokens.into_iter().peekable();
-
-    let mut instruction_set = Vec::new();
-    let mut labels = HashMap::new();
-
-    while let Some(cur_tkn) = token_iter.next() {
-        if let Token::Keyword(kw) = cur_tkn {
-            match kw {
-                Keyword::Add => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::Add,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::Subtract => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::Subtract,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::Multiply => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::Multiply,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::Divide => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::Divide,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::Modulus => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::Modulus,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::And => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::And,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::Or => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::Or,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::Not => instruction_set.push(Instruction::UnaryOp(
-                    UnOperator::Not,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::Xor => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::Xor,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::Equals => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::Equals,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::NotEquals => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::NotEquals,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::LessThan => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::LessThan,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::LessThanEqualTo => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::LessThanEqualTo,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::GreaterThan => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::GreaterThan,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::GreaterThanEqualTo => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::GreaterThanEqualTo,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::LeftShift => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::LeftShift,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::RightShift => instruction_set.push(Instruction::BinaryOp(
-                    BinOperator::RightShift,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::Increment => instruction_set.push(Instruction::UnaryOp(
-                    UnOperator::Increment,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-                Keyword::Decrement => instruction_set.push(Instruction::UnaryOp(
-                    UnOperator::Decrement,
-                    convert::get_register_name(token_iter.next())?,
-                    convert::get_register_name(token_iter.next())?,
-                )),
-
-                Keyword::Push => {
-                    let var_type = convert::get_variable_type(token_iter.next())?;
-                    let var_name = convert::get_ident_name(token_iter.next())?;
-
-                    instruction_set.push(Instruction::Push(var_type, var_name))
+mod link;
+
+mod resolve;
+
+mod stream;
+use stream::TokenStream;
+
+// struct types by name, a struct has to be declared before it is used
+pub type Structs = HashMap<Str, Rc<Struct>>;
+
+// what the statements declare, for the statements and passes that come after them
+#[derive(Default)]
+struct Declarations {
+    labels: Vec<(Str, usize, Span)>,
+    structs: Structs,
+    // variables whose `push` did not parse, their uses are not reported a second time
+    broken: HashSet<Name>,
+}
+
+pub fn parse(tokens: Vec<(Token, Span)>) -> Result<Program, Vec<JAPLError>> {
+    let mut tokens = TokenStream::new(tokens);
+
+    let mut program = Program::default();
+    let mut function = None;
+    let mut declarations = Declarations::default();
+    let mut errors = Vec::new();
+
+    while let Some((cur_tkn, span)) = tokens.next() {
+        let result = parse_statement(
+            cur_tkn,
+            span,
+            &mut tokens,
+            &mut program,
+            &mut function,
+            &mut declarations,
+        );
+
+        if let Err(e) = result {
+            errors.push(e);
+            tokens.synchronise();
+        }
+    }
+
+    // the missing `end` is added so that the passes below see a well formed body
+    if let Some((start, span)) = function {
+        errors.push(JAPLError::InvalidArgument(
+            "Missing keyword: end".into(),
+            span,
+        ));
+
+        let end = program.instructions.len();
+        program.instructions.push(Instruction::Return);
+
+        if let Instruction::Function(body_end, _) = &mut program.instructions[start] {
+            *body_end = end;
+        }
+    }
+
+    // the statements that did parse are still linked and resolved, so that every error in
+    // the file is reported at once
+    if let Err(e) = link::link(&mut program, declarations.labels) {
+        errors.extend(e);
+    }
+
+    if let Err(e) = resolve::resolve(&mut program, &declarations.broken) {
+        errors.extend(e);
+    }
+
+    if !errors.is_empty() {
+        errors.sort_by_key(|i| i.span().start);
+        return Err(errors);
+    }
+
+    Ok(program)
+}
+
+fn parse_statement(
+    cur_tkn: Token,
+    span: Span,
+    tokens: &mut TokenStream,
+    program: &mut Program,
+    function: &mut Option<(usize, Span)>,
+    declarations: &mut Declarations,
+) -> Result<(), JAPLError> {
+    if let Token::Keyword(kw) = cur_tkn {
+        if let Ok(op) = BinOperator::try_from(kw) {
+            let lhs = convert::get_source(tokens)?;
+            let rhs = convert::get_source(tokens)?;
+            let dst = convert::get_register_name(tokens)?;
+
+            let reg = match (&lhs, &rhs) {
+                (Source::Register(i), _) | (_, Source::Register(i)) => *i,
+                _ => {
+                    return Err(JAPLError::InvalidArgument(
+                        "At least one source operand must be a register".into(),
+                        span,
+                    ))
+                }
+            };
+
+            program.instructions.push(Instruction::BinaryOp(
+                op,
+                operand(lhs, reg)?,
+                operand(rhs, reg)?,
+                dst,
+            ));
+
+            return Ok(());
+        }
+
+        if let Ok(op) = UnOperator::try_from(kw) {
+            program.instructions.push(Instruction::UnaryOp(
+                op,
+                convert::get_register_name(tokens)?,
+                convert::get_register_name(tokens)?,
+            ));
+
+            return Ok(());
+        }
+
+        if let Ok(conversion) = Conversion::try_from(kw) {
+            let src = convert::get_register_name(tokens)?;
+            let dst = convert::get_register_name(tokens)?;
+
+            if !conversion.accepts(src, dst) {
+                return Err(JAPLError::InvalidArgument(
+                    format!("{:?} cannot convert {:?} to {:?}", kw, src, dst).into(),
+                    span,
+                ));
+            }
+
+            program
+                .instructions
+                .push(Instruction::Convert(conversion, src, dst));
+
+            return Ok(());
+        }
+
+        if let Ok(flag) = Flag::try_from(kw) {
+            let label = convert::get_label(tokens)?;
+            program
+                .instructions
+                .push(Instruction::JumpFlag(label, flag));
+
+            return Ok(());
+        }
+
+        match kw {
+            Keyword::Struct => {
+                let result = parse_struct(tokens, &mut declarations.structs);
+
+                // skip the rest of a broken declaration, its `end` would look like a stray one
+                if result.is_err() {
+                    tokens.synchronise();
+
+                    if let Some((Token::Keyword(Keyword::End), _)) = tokens.peek() {
+                        tokens.next();
+                    }
+                }
+
+                result?
+            }
+            Keyword::Push => {
+                let var_type = match convert::get_variable_type(tokens, &declarations.structs) {
+                    Ok(i) => i,
+                    Err(e) => {
+                        // the name is the last argument, whatever is wrong with the type
+                        let mut name = None;
+                        while let Some((tkn, _)) = tokens.next_argument() {
+                            if let Token::Identifier(i) = tkn {
+                                name = Some(i);
+                            }
+                        }
+
+                        declarations.broken.extend(name.map(Name::from));
+                        return Err(e);
+                    }
+                };
+
+                let var = convert::get_variable(tokens)?;
+
+                if let Variable::Name(_, fields, span) = &var {
+                    if !fields.is_empty() {
+                        return Err(JAPLError::InvalidArgument(
+                            "Fields cannot be pushed on their own".into(),
+                            *span,
+                        ));
+                    }
                 }
-                Keyword::Set => {
-                    let var_name = convert::get_ident_name(token_iter.next())?;
-                    let value = Value::try_from(
-                        token_iter
-                            .next()
-                            .ok_or(JAPLError::InvalidArgument("Missing token: Value".into()))?,
-                    )
-                    .map_err(|_| JAPLError::InvalidArgument("Expected token: Value".into()))?;
-
-                    instruction_set.push(Instruction::Set(var_name, value));
+
+                program.instructions.push(Instruction::Push(var_type, var))
+            }
+            Keyword::Set => {
+                let var = convert::get_variable(tokens)?;
+                let value = intern(convert::get_value(tokens)?, program);
+
+                program.instructions.push(Instruction::Set(var, value));
+            }
+
+            Keyword::Load => {
+                let (value, value_span) = (convert::get_value(tokens)?, tokens.last_span());
+
+                if let (Value::LValue(var), Some((Token::Symbol(Symbol::SquareOpen), _))) =
+                    (&value, tokens.peek())
+                {
+                    let element = element(var.clone(), convert::get_index(tokens)?);
+                    let reg = convert::get_register_name(tokens)?;
+
+                    program
+                        .instructions
+                        .push(Instruction::LoadElement(element, reg));
+                    return Ok(());
                 }
 
-                Keyword::Load => {
-                    let value = Value::try_from(
-                        token_iter
-                            .next()
-                            .ok_or(JAPLError::InvalidArgument("Missing token: Value".into()))?,
-                    )
-                    .map_err(|_| JAPLError::InvalidArgument("Expected token: Value".into()))?;
-                    let reg = convert::get_register_name(token_iter.next())?;
+                let reg = convert::get_register_name(tokens)?;
 
-                    instruction_set.push(Instruction::Load(value, reg));
+                if matches!(value, Value::RValue(Literal::String(_)))
+                    && reg.register_class() != RegisterClass::D
+                {
+                    return Err(JAPLError::InvalidArgument(
+                        "String literals can only be loaded into a d register".into(),
+                        value_span,
+                    ));
                 }
-                Keyword::Unload => {
-                    let reg = convert::get_register_name(token_iter.next())?;
-                    let var_name = convert::get_ident_name(token_iter.next())?;
 
-                    instruction_set.push(Instruction::Unload(reg, var_name));
+                let value = intern(value, program);
+
+                if let Value::RValue(literal) = &value {
+                    literal
+                        .as_bytes(reg.size())
+                        .map_err(|e| JAPLError::InvalidLiteral(e, value_span))?;
                 }
-                Keyword::Move => todo!(),
-                Keyword::Call => todo!(),
-                Keyword::CallIf => todo!(),
-                Keyword::Jump => {
-                    let label = convert::get_label_name(token_iter.next())?;
-                    instruction_set.push(Instruction::Jump(label));
+
+                program.instructions.push(Instruction::Load(value, reg));
+            }
+            Keyword::Unload => {
+                let reg = convert::get_register_name(tokens)?;
+                let var = convert::get_variable(tokens)?;
+
+                if let Some((Token::Symbol(Symbol::SquareOpen), _)) = tokens.peek() {
+                    let element = element(var, convert::get_index(tokens)?);
+                    program
+                        .instructions
+                        .push(Instruction::UnloadElement(reg, element));
+                } else {
+                    program.instructions.push(Instruction::Unload(reg, var));
                 }
+            }
+            Keyword::AddressOf => {
+                let var = convert::get_variable(tokens)?;
+                let dst = convert::get_pointer_register(tokens)?;
+
+                program.instructions.push(Instruction::AddressOf(var, dst));
+            }
+            Keyword::LoadPointer => {
+                let ptr = convert::get_pointer_register(tokens)?;
+                let dst = convert::get_register_name(tokens)?;
+
+                program
+                    .instructions
+                    .push(Instruction::LoadPointer(ptr, dst));
+            }
+            Keyword::StorePointer => {
+                let src = convert::get_register_name(tokens)?;
+                let ptr = convert::get_pointer_register(tokens)?;
+
+                program
+                    .instructions
+                    .push(Instruction::StorePointer(src, ptr));
+            }
+            Keyword::Alloc => {
+                let size = convert::get_pointer_register(tokens)?;
+                let dst = convert::get_pointer_register(tokens)?;
+
+                program.instructions.push(Instruction::Alloc(size, dst));
+            }
+            Keyword::Free => {
+                let ptr = convert::get_pointer_register(tokens)?;
+                program.instructions.push(Instruction::Free(ptr));
+            }
+            Keyword::Realloc => {
+                let size = convert::get_pointer_register(tokens)?;
+                let ptr = convert::get_pointer_register(tokens)?;
 
-                Keyword::JumpIf => {
-                    let label = convert::get_label_name(token_iter.next())?;
-                    let reg = convert::get_register_name(token_iter.next())?;
-                    instruction_set.push(Instruction::JumpIf(label, reg));
+                program.instructions.push(Instruction::Realloc(size, ptr));
+            }
+            Keyword::Move => {
+                let src = convert::get_register_name(tokens)?;
+                let dst = convert::get_register_name(tokens)?;
+
+                if src.register_class() != dst.register_class() {
+                    return Err(JAPLError::InvalidArgument(
+                        "Registers can only be moved within their class, use a conversion instead"
+                            .into(),
+                        tokens.last_span(),
+                    ));
                 }
-                _ => unimplemented!(),
+
+                program.instructions.push(Instruction::Move(src, dst));
             }
-        } else if let Token::Identifier(ident) = cur_tkn {
-            if !matches!(token_iter.peek(), Some(Token::Symbol(Symbol::Colon))) {
-                panic!("Labels must be follow by a ':'");
+            Keyword::Print => {
+                if let Some((Token::Keyword(_), _)) = tokens.peek() {
+                    let reg = convert::get_register_name(tokens)?;
+                    let value_type = reg.register_class().value_type();
+
+                    program
+                        .instructions
+                        .push(Instruction::PrintRegister(reg, value_type));
+                } else {
+                    let value = convert::get_value(tokens)?;
+                    program.instructions.push(Instruction::Print(value));
+                }
             }
+            Keyword::PrintString => {
+                let reg = convert::get_register_name(tokens)?;
 
-            labels.insert(ident, instruction_set.len().checked_sub(1).unwrap_or(0));
+                if reg.register_class() != RegisterClass::D {
+                    return Err(JAPLError::InvalidArgument(
+                        "Strings can only be printed from a d register".into(),
+                        tokens.last_span(),
+                    ));
+                }
+
+                program.instructions.push(Instruction::PrintString(reg));
+            }
+            Keyword::Read => {
+                if let Some((Token::Keyword(_), _)) = tokens.peek() {
+                    let reg = convert::get_register_name(tokens)?;
+                    let value_type = reg.register_class().value_type();
+
+                    program
+                        .instructions
+                        .push(Instruction::ReadRegister(reg, value_type));
+                } else {
+                    let var = convert::get_variable(tokens)?;
+                    program.instructions.push(Instruction::Read(var));
+                }
+            }
+            Keyword::PrintSigned | Keyword::PrintCharacter => {
+                let (reg, value_type) = convert::get_typed_register(tokens, kw)?;
+                program
+                    .instructions
+                    .push(Instruction::PrintRegister(reg, value_type));
+            }
+            Keyword::ReadSigned | Keyword::ReadCharacter => {
+                let (reg, value_type) = convert::get_typed_register(tokens, kw)?;
+                program
+                    .instructions
+                    .push(Instruction::ReadRegister(reg, value_type));
+            }
+            Keyword::ReadLine => {
+                let var = convert::get_variable(tokens)?;
+                program.instructions.push(Instruction::ReadLine(var));
+            }
+            Keyword::Function => {
+                if function.is_some() {
+                    return Err(JAPLError::InvalidArgument(
+                        "Functions cannot be nested".into(),
+                        span,
+                    ));
+                }
+
+                let name = convert::get_label_name(tokens)?;
+                declarations
+                    .labels
+                    .push((name, program.instructions.len(), tokens.last_span()));
+
+                *function = Some((program.instructions.len(), span));
+                program.instructions.push(Instruction::Function(0, 0));
+            }
+            Keyword::End => {
+                let (start, _) = function.take().ok_or(JAPLError::InvalidArgument(
+                    "Unexpected keyword: end".into(),
+                    span,
+                ))?;
+
+                let end = program.instructions.len();
+                program.instructions.push(Instruction::Return);
+
+                if let Instruction::Function(body_end, _) = &mut program.instructions[start] {
+                    *body_end = end;
+                }
+            }
+            Keyword::Call => {
+                let label = convert::get_label(tokens)?;
+                program.instructions.push(Instruction::Call(label));
+            }
+            Keyword::Extern => {
+                let name = convert::get_label_name(tokens)?;
+                program.instructions.push(Instruction::Extern(name));
+            }
+            Keyword::CallIf => {
+                let label = convert::get_label(tokens)?;
+                let reg = convert::get_register_name(tokens)?;
+                program.instructions.push(Instruction::CallIf(label, reg));
+            }
+            Keyword::Jump => {
+                let label = convert::get_label(tokens)?;
+                program.instructions.push(Instruction::Jump(label));
+            }
+
+            Keyword::JumpIf => {
+                let label = convert::get_label(tokens)?;
+                let reg = convert::get_register_name(tokens)?;
+                program.instructions.push(Instruction::JumpIf(label, reg));
+            }
+            Keyword::Halt => program.instructions.push(Instruction::Exit(None)),
+            Keyword::Exit => {
+                let reg = convert::get_register_name(tokens)?;
+
+                if !reg.register_class().is_integer() {
+                    return Err(JAPLError::InvalidArgument(
+                        "The exit status must be an integer register".into(),
+                        tokens.last_span(),
+                    ));
+                }
+
+                program.instructions.push(Instruction::Exit(Some(reg)));
+            }
+            _ => {
+                return Err(JAPLError::InvalidArgument(
+                    format!("Unexpected keyword: {:?}", kw).into(),
+                    span,
+                ))
+            }
+        }
+    } else if let Token::Identifier(ident) = cur_tkn {
+        if !matches!(tokens.peek(), Some((Token::Symbol(Symbol::Colon), _))) {
+            return Err(JAPLError::InvalidArgument(
+                "Labels must be followed by a ':'".into(),
+                span,
+            ));
+        }
+
+        tokens.next();
+
+        declarations
+            .labels
+            .push((ident, program.instructions.len(), span));
+    } else {
+        return Err(JAPLError::InvalidArgument(
+            format!("Unexpected token: {:?}", cur_tkn).into(),
+            span,
+        ));
+    }
+
+    Ok(())
+}
+
+// `struct Name type field ... end`, the fields are laid out in the order they are declared
+fn parse_struct(tokens: &mut TokenStream, structs: &mut Structs) -> Result<(), JAPLError> {
+    let (name, name_span) = convert::get_name(tokens, "Struct Name")?;
+
+    if structs.contains_key(&name) {
+        return Err(JAPLError::InvalidArgument(
+            format!("Duplicate struct: {}", name).into(),
+            name_span,
+        ));
+    }
+
+    let mut fields: Vec<(Str, Type)> = Vec::new();
+
+    while !matches!(tokens.peek(), Some((Token::Keyword(Keyword::End), _))) {
+        let field_type = convert::get_variable_type(tokens, structs)?;
+        let (field, field_span) = convert::get_name(tokens, "Field Name")?;
+
+        if fields.iter().any(|(i, _)| *i == field) {
+            return Err(JAPLError::InvalidArgument(
+                format!("Duplicate field: {}", field).into(),
+                field_span,
+            ));
         }
+
+        fields.push((field, field_type));
     }
 
-    Ok((instruction_set, labels))
+    // a struct without fields would take up no memory at all
+    if fields.is_empty() {
+        return Err(JAPLError::InvalidArgument(
+            format!("Struct {} has no fields", name).into(),
+            name_span,
+        ));
+    }
+
+    let record = Struct::new(name.clone(), fields);
+    if record.size > MAX_TYPE_SIZE {
+        return Err(JAPLError::InvalidArgument(
+            format!("Struct {} is too large", name).into(),
+            name_span,
+        ));
+    }
+
+    tokens.next();
+    structs.insert(name, Rc::new(record));
+
+    Ok(())
+}
+
+// an indexed access, named the way it was written so runtime errors can point at it
+fn element(array: Variable, index: Operand) -> Element {
+    let name = match &array {
+        Variable::Name(name, fields, _) => std::iter::once(&**name)
+            .chain(fields.iter().map(|i| &**i))
+            .collect::<Vec<_>>()
+            .join(".")
+            .into(),
+        Variable::Slot(_) => unreachable!("variables are only resolved after parsing"),
+    };
+
+    Element { name, array, index }
+}
+
+// moves string literals into the data segment, leaving their address behind
+fn intern(value: Value, program: &mut Program) -> Value {
+    match value {
+        Value::RValue(Literal::String(i)) => {
+            Value::RValue(Literal::Integer(program.intern(i.as_bytes()) as i128, None))
+        }
+        _ => value,
+    }
+}
+
+// range-checks an immediate against the class of the register it is paired with
+fn operand(source: Source, reg: RegisterName) -> Result<Operand, JAPLError> {
+    let (literal, span) = match source {
+        Source::Register(i) => return Ok(Operand::Register(i)),
+        Source::Literal(literal, span) => (literal, span),
+    };
+
+    let class = reg.register_class();
+    let bits = match (class, &literal) {
+        (RegisterClass::F, Literal::Float(i)) => (*i as f32).to_bits() as u64,
+        (RegisterClass::F, Literal::Integer(i, None)) => (*i as f32).to_bits() as u64,
+        (RegisterClass::G, Literal::Float(i)) => i.to_bits(),
+        (RegisterClass::G, Literal::Integer(i, None)) => (*i as f64).to_bits(),
+        (RegisterClass::I, Literal::Boolean(i)) => *i as u64,
+        (_, Literal::Integer(..) | Literal::Character(_)) if class.is_integer() => {
+            let bytes = literal
+                .as_bytes(reg.size())
+                .map_err(|e| JAPLError::InvalidLiteral(e, span))?;
+
+            match bytes.len() {
+                1 => bytes[0] as u64,
+                2 => u16::from_ne_bytes([bytes[0], bytes[1]]) as u64,
+                4 => u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64,
+                _ => u64::from_ne_bytes(bytes.try_into().expect("size is checked by as_bytes")),
+            }
+        }
+        _ => {
+            return Err(JAPLError::InvalidLiteral(
+                format!("{:?} cannot be used with {:?} registers", literal, class).into(),
+                span,
+            ))
+        }
+    };
+
+    Ok(Operand::Immediate(bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(source: &str) -> Vec<String> {
+        match crate::compile(source) {
+            Ok(_) => Vec::new(),
+            Err(e) => e.errors.iter().map(|i| i.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn lays_out_struct_fields_in_order() {
+        let program = crate::compile(
+            "struct Point
+int32 x
+int16 y
+end
+struct Line
+Point from
+Point to
+uint8 color
+end
+push Line l
+load l.to.y b0",
+        )
+        .unwrap();
+
+        assert_eq!(program.globals["l"].var_type.size(), 13);
+
+        let Instruction::Load(Value::LValue(Variable::Slot(slot)), _) = &program.instructions[1]
+        else {
+            panic!("the load was not resolved");
+        };
+
+        assert_eq!((slot.offset, &slot.var_type), (10, &Type::Int16));
+    }
+
+    #[test]
+    fn rejects_empty_and_oversized_structs() {
+        assert_eq!(
+            errors("struct Empty\nend"),
+            ["Invalid arguments passed: Struct Empty has no fields"]
+        );
+        assert_eq!(
+            errors("struct Big\nint8[4294967295] bytes\nint8 more\nend"),
+            ["Invalid arguments passed: Struct Big is too large"]
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_and_unknown_fields() {
+        assert_eq!(
+            errors("struct Point\nint32 x\nint32 x\nend"),
+            ["Invalid arguments passed: Duplicate field: x"]
+        );
+        assert_eq!(
+            errors("struct Point\nint32 x\nend\npush Point p\nload p.y c0"),
+            ["Invalid arguments passed: Struct Point has no field y"]
+        );
+    }
+
+    #[test]
+    fn reports_link_and_resolve_errors_alongside_parse_errors() {
+        assert_eq!(
+            errors("push int32 x\nlaod x c0\njump nowhere\nload y c0"),
+            [
+                "Invalid arguments passed: Labels must be followed by a ':'",
+                "Undefined label: nowhere",
+                "Invalid arguments passed: Undeclared variable: y",
+            ]
+        );
+    }
+
+    #[test]
+    fn does_not_report_the_uses_of_a_variable_whose_push_failed() {
+        assert_eq!(
+            errors("push Nope[3] xs\nload xs[0] c0\nreadline xs"),
+            ["Invalid arguments passed: Unknown struct: Nope"]
+        );
+    }
+
+    #[test]
+    fn resolves_a_fn_that_is_missing_its_end() {
+        assert_eq!(
+            errors("fn body\npush int32 x\nload y c0"),
+            [
+                "Invalid arguments passed: Missing keyword: end",
+                "Invalid arguments passed: Undeclared variable: y",
+            ]
+        );
+    }
 }
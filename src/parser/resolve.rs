@@ -0,0 +1,320 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::alias::{Name, Str};
+use crate::error::JAPLError;
+use crate::lexer::Span;
+use crate::runtime::{Instruction, Operand, Program, Scope, Slot, Type, Value, Variable};
+
+// gives every variable a fixed offset in its frame and replaces each name with its slot
+pub fn resolve(program: &mut Program, broken: &HashSet<Name>) -> Result<(), Vec<JAPLError>> {
+    let mut errors = Vec::new();
+
+    // globals are laid out first, functions may use any of them
+    let mut global_slots = HashMap::new();
+    let mut pc = 0;
+
+    while pc < program.instructions.len() {
+        match &program.instructions[pc] {
+            Instruction::Function(body_end, _) => pc = *body_end,
+            Instruction::Push(var_type, Variable::Name(name, ..)) => {
+                let slot = Slot {
+                    scope: Scope::Global,
+                    offset: program.globals_size,
+                    var_type: var_type.clone(),
+                };
+
+                program.globals_size += var_type.size();
+                program.globals.insert(name.clone(), slot.clone());
+                global_slots.insert(pc, slot);
+            }
+            _ => (),
+        }
+
+        pc += 1;
+    }
+
+    // top level code only sees the globals pushed before it
+    let mut visible: HashMap<Name, Slot> = HashMap::new();
+    let mut function: Option<(usize, HashMap<Name, Slot>, usize)> = None;
+
+    for pc in 0..program.instructions.len() {
+        let instruction = &mut program.instructions[pc];
+
+        match instruction {
+            Instruction::Function(..) => {
+                function = Some((pc, HashMap::new(), 0));
+                continue;
+            }
+            Instruction::Return => {
+                if let Some((start, _, size)) = function.take() {
+                    if let Instruction::Function(_, frame_size) = &mut program.instructions[start] {
+                        *frame_size = size;
+                    }
+                }
+
+                continue;
+            }
+            Instruction::Push(var_type, var) => {
+                let Variable::Name(name, ..) = var else {
+                    continue;
+                };
+
+                let slot = match &mut function {
+                    Some((_, locals, size)) => {
+                        let slot = Slot {
+                            scope: Scope::Local,
+                            offset: *size,
+                            var_type: var_type.clone(),
+                        };
+
+                        *size += var_type.size();
+                        locals.insert(name.clone(), slot.clone());
+                        slot
+                    }
+                    None => {
+                        let slot = global_slots[&pc].clone();
+                        visible.insert(name.clone(), slot.clone());
+                        slot
+                    }
+                };
+
+                *var = Variable::Slot(slot);
+                continue;
+            }
+            _ => (),
+        }
+
+        // a literal has no fields or elements, so it cannot initialise a whole struct or array,
+        // and it has to fit the type of the variable it is stored in
+        let literal_span = match instruction {
+            Instruction::Set(Variable::Name(_, _, span), Value::RValue(_)) => Some(*span),
+            _ => None,
+        };
+
+        let element_span = match instruction {
+            Instruction::LoadElement(element, _) | Instruction::UnloadElement(_, element) => {
+                match &element.array {
+                    Variable::Name(_, _, span) => Some(*span),
+                    Variable::Slot(_) => None,
+                }
+            }
+            _ => None,
+        };
+
+        for var in variables(instruction) {
+            let Variable::Name(name, fields, span) = var else {
+                continue;
+            };
+
+            let slot = match &function {
+                Some((_, locals, _)) => locals.get(name).or(program.globals.get(name)),
+                None => visible.get(name),
+            };
+
+            let Some(slot) = slot else {
+                // the `push` of a broken variable has already been reported
+                if broken.contains(name) {
+                    continue;
+                }
+
+                errors.push(JAPLError::InvalidArgument(
+                    format!("Undeclared variable: {}", name).into(),
+                    *span,
+                ));
+                continue;
+            };
+
+            match field_slot(slot.clone(), fields, *span) {
+                Ok(slot) => *var = Variable::Slot(slot),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        if let Some(span) = element_span {
+            if let Err(e) = check_element(instruction, span) {
+                errors.push(e);
+            }
+        }
+
+        if let (Some(span), Instruction::Set(Variable::Slot(slot), Value::RValue(literal))) =
+            (literal_span, &*instruction)
+        {
+            match &slot.var_type {
+                Type::Struct(record) => errors.push(JAPLError::InvalidArgument(
+                    format!("Struct {} cannot be set from a literal", record.name).into(),
+                    span,
+                )),
+                Type::Array(..) => errors.push(JAPLError::InvalidArgument(
+                    "Arrays cannot be set from a literal".into(),
+                    span,
+                )),
+                var_type => {
+                    if let Err(e) = literal.check_type(var_type) {
+                        errors.push(JAPLError::InvalidLiteral(e, span));
+                    }
+                }
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(())
+}
+
+// narrows the slot of a struct down to one of its fields, and so on for nested structs
+fn field_slot(mut slot: Slot, fields: &[Str], span: Span) -> Result<Slot, JAPLError> {
+    for name in fields {
+        let Type::Struct(record) = &slot.var_type else {
+            return Err(JAPLError::InvalidArgument(
+                format!("{:?} has no field {}", slot.var_type, name).into(),
+                span,
+            ));
+        };
+
+        let Some(field) = record.field(name) else {
+            return Err(JAPLError::InvalidArgument(
+                format!("Struct {} has no field {}", record.name, name).into(),
+                span,
+            ));
+        };
+
+        slot.offset += field.offset;
+        slot.var_type = field.field_type.clone();
+    }
+
+    Ok(slot)
+}
+
+// indexing needs an array, and a literal index can be checked before the program runs
+fn check_element(instruction: &Instruction, span: Span) -> Result<(), JAPLError> {
+    let (Instruction::LoadElement(element, _) | Instruction::UnloadElement(_, element)) =
+        instruction
+    else {
+        return Ok(());
+    };
+
+    let Variable::Slot(slot) = &element.array else {
+        return Ok(());
+    };
+
+    let Type::Array(_, len) = &slot.var_type else {
+        return Err(JAPLError::InvalidArgument(
+            format!("{} is not an array", element.name).into(),
+            span,
+        ));
+    };
+
+    match element.index {
+        Operand::Immediate(i) if i >= *len as u64 => Err(JAPLError::InvalidArgument(
+            format!(
+                "Index {} is out of bounds for {} of length {}",
+                i, element.name, len
+            )
+            .into(),
+            span,
+        )),
+        _ => Ok(()),
+    }
+}
+
+// every variable an instruction reads or writes, apart from the one a `push` declares
+fn variables(instruction: &mut Instruction) -> Vec<&mut Variable> {
+    match instruction {
+        Instruction::Set(var, Value::LValue(value)) => vec![var, value],
+        Instruction::Set(var, _)
+        | Instruction::Unload(_, var)
+        | Instruction::AddressOf(var, _)
+        | Instruction::Read(var)
+        | Instruction::ReadLine(var)
+        | Instruction::Load(Value::LValue(var), _)
+        | Instruction::Print(Value::LValue(var)) => vec![var],
+        Instruction::LoadElement(element, _) | Instruction::UnloadElement(_, element) => {
+            vec![&mut element.array]
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(source: &str) -> Vec<String> {
+        match crate::compile(source) {
+            Ok(_) => Vec::new(),
+            Err(e) => e.errors.iter().map(|i| i.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn rejects_literals_outside_the_range_of_their_variable() {
+        assert_eq!(
+            errors("push uint8 u\nset u -1"),
+            ["Invalid literal: integer literal `-1` is out of range for Uint8"]
+        );
+        assert_eq!(
+            errors("push int8 s\nset s 200"),
+            ["Invalid literal: integer literal `200` is out of range for Int8"]
+        );
+        assert!(errors("push uint8 u\nset u 255\npush int8 s\nset s -128").is_empty());
+    }
+
+    #[test]
+    fn rejects_literals_of_another_kind() {
+        assert_eq!(errors("push int32 x\nset x 1.5").len(), 1);
+        assert_eq!(errors("push float64 x\nset x 1").len(), 1);
+        assert_eq!(errors("push char x\nset x true").len(), 1);
+    }
+
+    #[test]
+    fn lays_out_globals_and_locals_in_their_own_frames() {
+        let program = crate::compile(
+            "push int32 first
+fn one
+push int64 wide
+push int8 narrow
+load narrow a0
+end
+push int16 second",
+        )
+        .unwrap();
+
+        assert_eq!(program.globals_size, 6);
+        assert_eq!(program.globals["second"].offset, 4);
+        assert!(matches!(
+            program.instructions[1],
+            Instruction::Function(5, 9)
+        ));
+
+        let Instruction::Load(Value::LValue(Variable::Slot(slot)), _) = &program.instructions[4]
+        else {
+            panic!("the load was not resolved");
+        };
+
+        assert_eq!((slot.scope, slot.offset), (Scope::Local, 8));
+    }
+
+    #[test]
+    fn rejects_variables_that_are_not_declared_yet_or_in_another_fn() {
+        assert_eq!(
+            errors("load x c0\npush int32 x"),
+            ["Invalid arguments passed: Undeclared variable: x"]
+        );
+        assert_eq!(
+            errors("fn one\npush int32 x\nend\nfn two\nload x c0\nend"),
+            ["Invalid arguments passed: Undeclared variable: x"]
+        );
+        assert_eq!(
+            errors("fn one\npush int32 x\nend\nload x c0"),
+            ["Invalid arguments passed: Undeclared variable: x"]
+        );
+    }
+
+    #[test]
+    fn lets_functions_use_globals_pushed_after_them() {
+        assert!(errors("fn one\nload x c0\nend\npush int32 x").is_empty());
+    }
+}
@@ -0,0 +1,70 @@
+use crate::lexer::{Span, Symbol, Token};
+
+pub struct TokenStream {
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+}
+
+impl TokenStream {
+    pub fn new(tokens: Vec<(Token, Span)>) -> Self {
+        let tokens = tokens
+            .into_iter()
+            .filter(|(tkn, _)| !matches!(tkn, Token::Comment(_)))
+            .collect();
+
+        Self { tokens, pos: 0 }
+    }
+
+    pub fn peek(&self) -> Option<&(Token, Span)> {
+        self.tokens.get(self.pos)
+    }
+
+    // span of the most recently consumed token, used to point at the end of an
+    // instruction that is missing an argument
+    pub fn last_span(&self) -> Span {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map(|(_, span)| *span)
+            .unwrap_or_default()
+    }
+
+    // the next argument of the current instruction, or `None` if the next token
+    // already starts a new statement
+    pub fn next_argument(&mut self) -> Option<(Token, Span)> {
+        if self.at_statement() {
+            return None;
+        }
+
+        self.next()
+    }
+
+    // skips ahead to the next instruction keyword or label after a parse error
+    pub fn synchronise(&mut self) {
+        while self.peek().is_some() && !self.at_statement() {
+            self.pos += 1;
+        }
+    }
+
+    fn at_statement(&self) -> bool {
+        match self.peek() {
+            Some((Token::Keyword(kw), _)) => kw.starts_statement(),
+            Some((Token::Identifier(_), _)) => matches!(
+                self.tokens.get(self.pos + 1),
+                Some((Token::Symbol(Symbol::Colon), _))
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl Iterator for TokenStream {
+    type Item = (Token, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.tokens.get(self.pos).cloned()?;
+
+        self.pos += 1;
+        Some(next)
+    }
+}
@@ -0,0 +1,324 @@
+use super::register::Flags;
+use super::BinOperator;
+use crate::error::Cause;
+
+// integer registers of every width are operated on as `u64`s holding the low `bits` bits
+
+// `carry` is the incoming carry flag, only read by `adc` and `sbb`
+pub fn integer_op(
+    op: BinOperator,
+    lhs: u64,
+    rhs: u64,
+    carry: bool,
+    bits: u32,
+) -> Result<u64, Cause> {
+    let result = match op {
+        BinOperator::Add => unsigned(lhs as i128 + rhs as i128, bits)?,
+        BinOperator::Subtract => unsigned(lhs as i128 - rhs as i128, bits)?,
+        BinOperator::Multiply => {
+            let product = lhs as u128 * rhs as u128;
+
+            if product > mask(bits) as u128 {
+                return Err(Cause::IntegerOverflow);
+            }
+
+            product as u64
+        }
+        BinOperator::Divide => lhs / non_zero(rhs)?,
+        BinOperator::Modulus => lhs % non_zero(rhs)?,
+
+        BinOperator::LeftShift => {
+            let amount = shift_amount(rhs, bits)?;
+
+            if shifted_out(lhs, amount as u64, bits) {
+                return Err(Cause::IntegerOverflow);
+            }
+
+            lhs << amount
+        }
+        BinOperator::RightShift => lhs >> shift_amount(rhs, bits)?,
+
+        BinOperator::And => lhs & rhs,
+        BinOperator::Or => lhs | rhs,
+        BinOperator::Xor => lhs ^ rhs,
+
+        BinOperator::SignedAdd => signed(to_signed(lhs, bits) + to_signed(rhs, bits), bits)?,
+        BinOperator::SignedSubtract => signed(to_signed(lhs, bits) - to_signed(rhs, bits), bits)?,
+        BinOperator::SignedMultiply => signed(to_signed(lhs, bits) * to_signed(rhs, bits), bits)?,
+        BinOperator::SignedDivide => {
+            signed(to_signed(lhs, bits) / to_signed(non_zero(rhs)?, bits), bits)?
+        }
+        BinOperator::SignedModulus => {
+            signed(to_signed(lhs, bits) % to_signed(non_zero(rhs)?, bits), bits)?
+        }
+        BinOperator::ArithmeticRightShift => {
+            (to_signed(lhs, bits) >> shift_amount(rhs, bits)?) as u64 & mask(bits)
+        }
+
+        BinOperator::WrappingAdd => lhs.wrapping_add(rhs) & mask(bits),
+        BinOperator::WrappingSubtract => lhs.wrapping_sub(rhs) & mask(bits),
+        BinOperator::WrappingMultiply => lhs.wrapping_mul(rhs) & mask(bits),
+        BinOperator::WrappingLeftShift => (lhs << (rhs % bits as u64)) & mask(bits),
+
+        BinOperator::SaturatingAdd => (lhs as u128 + rhs as u128).min(mask(bits) as u128) as u64,
+        BinOperator::SaturatingSubtract => lhs.saturating_sub(rhs),
+        BinOperator::SaturatingMultiply => {
+            (lhs as u128 * rhs as u128).min(mask(bits) as u128) as u64
+        }
+        BinOperator::SaturatingLeftShift => {
+            if lhs == 0 {
+                0
+            } else if rhs >= bits as u64 || lhs.leading_zeros() < (64 - bits) + rhs as u32 {
+                mask(bits)
+            } else {
+                lhs << rhs
+            }
+        }
+
+        BinOperator::AddCarry => (lhs as u128 + rhs as u128 + carry as u128) as u64 & mask(bits),
+        BinOperator::SubtractBorrow => {
+            lhs.wrapping_sub(rhs).wrapping_sub(carry as u64) & mask(bits)
+        }
+
+        _ => unreachable!("{:?} is a comparison", op),
+    };
+
+    Ok(result)
+}
+
+// carry is set when the unsigned result did not fit (or borrowed), overflow when the signed one did not
+pub fn integer_flags(
+    op: BinOperator,
+    lhs: u64,
+    rhs: u64,
+    carry: bool,
+    result: u64,
+    bits: u32,
+) -> Flags {
+    let carry_in = matches!(op, BinOperator::AddCarry | BinOperator::SubtractBorrow) && carry;
+    let (ulhs, urhs, c) = (lhs as i128, rhs as i128, carry_in as i128);
+    let (slhs, srhs) = (to_signed(lhs, bits), to_signed(rhs, bits));
+
+    let (carry, overflow) = match op {
+        BinOperator::Add
+        | BinOperator::SignedAdd
+        | BinOperator::WrappingAdd
+        | BinOperator::SaturatingAdd
+        | BinOperator::AddCarry => (
+            ulhs + urhs + c > mask(bits) as i128,
+            !fits_signed(slhs + srhs + c, bits),
+        ),
+        BinOperator::Subtract
+        | BinOperator::SignedSubtract
+        | BinOperator::WrappingSubtract
+        | BinOperator::SaturatingSubtract
+        | BinOperator::SubtractBorrow => (ulhs - urhs - c < 0, !fits_signed(slhs - srhs - c, bits)),
+        BinOperator::Multiply
+        | BinOperator::SignedMultiply
+        | BinOperator::WrappingMultiply
+        | BinOperator::SaturatingMultiply => (
+            lhs as u128 * rhs as u128 > mask(bits) as u128,
+            !fits_signed(slhs * srhs, bits),
+        ),
+        BinOperator::LeftShift | BinOperator::SaturatingLeftShift => {
+            (shifted_out(lhs, rhs, bits), false)
+        }
+        BinOperator::WrappingLeftShift => (shifted_out(lhs, rhs % bits as u64, bits), false),
+        _ => (false, false),
+    };
+
+    Flags {
+        zero: result == 0,
+        carry,
+        overflow,
+        sign: result >> (bits - 1) & 1 == 1,
+    }
+}
+
+pub fn integer_cmp(op: BinOperator, lhs: u64, rhs: u64, bits: u32) -> bool {
+    match op {
+        BinOperator::Equals => lhs == rhs,
+        BinOperator::NotEquals => lhs != rhs,
+        BinOperator::LessThan => lhs < rhs,
+        BinOperator::GreaterThan => lhs > rhs,
+        BinOperator::LessThanEqualTo => lhs <= rhs,
+        BinOperator::GreaterThanEqualTo => lhs >= rhs,
+
+        BinOperator::SignedLessThan => to_signed(lhs, bits) < to_signed(rhs, bits),
+        BinOperator::SignedGreaterThan => to_signed(lhs, bits) > to_signed(rhs, bits),
+        BinOperator::SignedLessThanEqualTo => to_signed(lhs, bits) <= to_signed(rhs, bits),
+        BinOperator::SignedGreaterThanEqualTo => to_signed(lhs, bits) >= to_signed(rhs, bits),
+
+        _ => unreachable!("{:?} is not a comparison", op),
+    }
+}
+
+pub fn mask(bits: u32) -> u64 {
+    u64::MAX >> (64 - bits)
+}
+
+// sign-extends the low `bits` bits
+pub fn to_signed(value: u64, bits: u32) -> i128 {
+    (((value << (64 - bits)) as i64) >> (64 - bits)) as i128
+}
+
+fn unsigned(value: i128, bits: u32) -> Result<u64, Cause> {
+    if value < 0 || value > mask(bits) as i128 {
+        return Err(Cause::IntegerOverflow);
+    }
+
+    Ok(value as u64)
+}
+
+fn signed(value: i128, bits: u32) -> Result<u64, Cause> {
+    if !fits_signed(value, bits) {
+        return Err(Cause::IntegerOverflow);
+    }
+
+    Ok(value as u64 & mask(bits))
+}
+
+fn fits_signed(value: i128, bits: u32) -> bool {
+    let min = -(1i128 << (bits - 1));
+    let max = (1i128 << (bits - 1)) - 1;
+
+    value >= min && value <= max
+}
+
+// whether shifting left by `amount` loses any set bits
+fn shifted_out(value: u64, amount: u64, bits: u32) -> bool {
+    match amount {
+        0 => false,
+        i if i >= bits as u64 => value != 0,
+        i => value >> (bits as u64 - i) != 0,
+    }
+}
+
+fn non_zero(value: u64) -> Result<u64, Cause> {
+    if value == 0 {
+        return Err(Cause::DivisionByZero);
+    }
+
+    Ok(value)
+}
+
+fn shift_amount(value: u64, bits: u32) -> Result<u32, Cause> {
+    if value >= bits as u64 {
+        return Err(Cause::IntegerOverflow);
+    }
+
+    Ok(value as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(op: BinOperator, lhs: u64, rhs: u64, bits: u32) -> Result<u64, Cause> {
+        integer_op(op, lhs, rhs, false, bits)
+    }
+
+    #[test]
+    fn traps_on_overflow() {
+        assert_eq!(op(BinOperator::Add, 255, 1, 8), Err(Cause::IntegerOverflow));
+        assert_eq!(
+            op(BinOperator::Subtract, 0, 1, 32),
+            Err(Cause::IntegerOverflow)
+        );
+        assert_eq!(
+            op(BinOperator::Multiply, 1 << 32, 1 << 32, 64),
+            Err(Cause::IntegerOverflow)
+        );
+        assert_eq!(
+            op(BinOperator::SignedAdd, 127, 1, 8),
+            Err(Cause::IntegerOverflow)
+        );
+        assert_eq!(
+            op(BinOperator::SignedSubtract, 0x80, 1, 8),
+            Err(Cause::IntegerOverflow)
+        );
+        // -128 / -1 is the one signed division that does not fit
+        assert_eq!(
+            op(BinOperator::SignedDivide, 0x80, 0xFF, 8),
+            Err(Cause::IntegerOverflow)
+        );
+        assert_eq!(
+            op(BinOperator::LeftShift, 0x80, 1, 8),
+            Err(Cause::IntegerOverflow)
+        );
+        assert_eq!(
+            op(BinOperator::RightShift, 1, 8, 8),
+            Err(Cause::IntegerOverflow)
+        );
+    }
+
+    #[test]
+    fn traps_on_division_by_zero() {
+        for i in [
+            BinOperator::Divide,
+            BinOperator::Modulus,
+            BinOperator::SignedDivide,
+            BinOperator::SignedModulus,
+        ] {
+            assert_eq!(op(i, 7, 0, 32), Err(Cause::DivisionByZero));
+        }
+    }
+
+    #[test]
+    fn operates_on_signed_values() {
+        // -3 in 8 bits is 0xFD
+        assert_eq!(op(BinOperator::SignedAdd, 0xFD, 1, 8), Ok(0xFE));
+        assert_eq!(op(BinOperator::SignedMultiply, 0xFD, 3, 8), Ok(0xF7));
+        assert_eq!(op(BinOperator::SignedDivide, 0xF9, 2, 8), Ok(0xFD));
+        assert_eq!(op(BinOperator::SignedModulus, 0xF9, 2, 8), Ok(0xFF));
+        assert_eq!(op(BinOperator::ArithmeticRightShift, 0xF0, 2, 8), Ok(0xFC));
+        assert_eq!(op(BinOperator::RightShift, 0xF0, 2, 8), Ok(0x3C));
+
+        assert!(integer_cmp(BinOperator::SignedLessThan, 0xFD, 1, 8));
+        assert!(!integer_cmp(BinOperator::LessThan, 0xFD, 1, 8));
+        assert!(integer_cmp(
+            BinOperator::SignedGreaterThanEqualTo,
+            0,
+            u64::MAX,
+            64
+        ));
+    }
+
+    #[test]
+    fn wraps_around() {
+        assert_eq!(op(BinOperator::WrappingAdd, 255, 2, 8), Ok(1));
+        assert_eq!(op(BinOperator::WrappingSubtract, 0, 1, 16), Ok(0xFFFF));
+        assert_eq!(op(BinOperator::WrappingMultiply, 0x80, 3, 8), Ok(0x80));
+        assert_eq!(op(BinOperator::WrappingLeftShift, 0x81, 1, 8), Ok(0x02));
+        assert_eq!(op(BinOperator::WrappingAdd, u64::MAX, 1, 64), Ok(0));
+    }
+
+    #[test]
+    fn saturates() {
+        assert_eq!(op(BinOperator::SaturatingAdd, 250, 10, 8), Ok(255));
+        assert_eq!(
+            op(BinOperator::SaturatingAdd, u64::MAX, 1, 64),
+            Ok(u64::MAX)
+        );
+        assert_eq!(op(BinOperator::SaturatingSubtract, 3, 10, 32), Ok(0));
+        assert_eq!(
+            op(BinOperator::SaturatingMultiply, 0x100, 0x100, 16),
+            Ok(0xFFFF)
+        );
+        assert_eq!(op(BinOperator::SaturatingLeftShift, 0x40, 2, 8), Ok(0xFF));
+        assert_eq!(op(BinOperator::SaturatingLeftShift, 0x10, 2, 8), Ok(0x40));
+        assert_eq!(op(BinOperator::SaturatingLeftShift, 0, 100, 8), Ok(0));
+    }
+
+    #[test]
+    fn flags_the_carry_and_overflow_of_a_wrapped_result() {
+        let flags = integer_flags(BinOperator::WrappingAdd, 255, 1, false, 0, 8);
+        assert!(flags.carry && flags.zero && !flags.overflow && !flags.sign);
+
+        let flags = integer_flags(BinOperator::WrappingAdd, 127, 1, false, 128, 8);
+        assert!(!flags.carry && flags.overflow && flags.sign);
+
+        let flags = integer_flags(BinOperator::WrappingSubtract, 0, 1, false, 255, 8);
+        assert!(flags.carry && !flags.overflow && flags.sign);
+    }
+}
@@ -0,0 +1,22 @@
+#[derive(Debug)]
+pub struct Frame {
+    pub return_addr: usize,
+    pub base: usize,
+    // frames are laid out when the program is resolved, so their size is fixed
+    pub size: usize,
+}
+
+impl Frame {
+    pub fn new(return_addr: usize, base: usize, size: usize) -> Self {
+        Self {
+            return_addr,
+            base,
+            size,
+        }
+    }
+
+    // offset of the first free byte after this frame
+    pub fn top(&self) -> usize {
+        self.base + self.size
+    }
+}
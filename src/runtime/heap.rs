@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::error::Cause;
+
+// heap addresses start far above anything the stack can reach, so the two never overlap
+pub const HEAP_BASE: usize = 1 << 40;
+
+// freed blocks kept to report stale pointers, past this the oldest are forgotten and a
+// pointer into one is only reported as out of bounds
+const FREED_HISTORY: usize = 1 << 16;
+
+// an allocation that was never freed
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Leak {
+    pub address: usize,
+    pub size: usize,
+    // pc of the `alloc` that made it
+    pub site: usize,
+}
+
+#[derive(Debug)]
+struct Block {
+    bytes: Vec<u8>,
+    site: usize,
+    // pc of the `free`, freed blocks are kept so stale pointers can be reported
+    freed: Option<usize>,
+}
+
+// addresses are never reused, every pointer into a freed block stays detectable
+#[derive(Debug)]
+pub struct Heap {
+    blocks: BTreeMap<usize, Block>,
+    // addresses of the freed blocks, oldest first
+    freed: VecDeque<usize>,
+    next: usize,
+    live: usize,
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Self {
+            blocks: BTreeMap::new(),
+            freed: VecDeque::new(),
+            next: HEAP_BASE,
+            live: 0,
+        }
+    }
+
+    // bytes held by blocks that have not been freed
+    pub fn live(&self) -> usize {
+        self.live
+    }
+
+    pub fn alloc(&mut self, size: usize, site: usize) -> Result<usize, Cause> {
+        let bytes = zeroed(size)?;
+        self.insert(bytes, site)
+    }
+
+    pub fn free(&mut self, address: usize, site: usize) -> Result<(), Cause> {
+        let block = self
+            .blocks
+            .get_mut(&address)
+            .ok_or(Cause::InvalidFree(address))?;
+
+        if let Some(freed) = block.freed {
+            return Err(Cause::DoubleFree {
+                address,
+                allocated: block.site,
+                freed,
+            });
+        }
+
+        block.freed = Some(site);
+        self.live -= block.bytes.len();
+
+        // the contents are gone, only the bookkeeping is kept
+        block.bytes = Vec::new();
+
+        self.freed.push_back(address);
+        if self.freed.len() > FREED_HISTORY {
+            let oldest = self.freed.pop_front().expect("the history is not empty");
+            self.blocks.remove(&oldest);
+        }
+
+        Ok(())
+    }
+
+    // moves the contents into a new block of `size` bytes and frees the old one
+    pub fn realloc(&mut self, address: usize, size: usize, site: usize) -> Result<usize, Cause> {
+        if !self.blocks.contains_key(&address) {
+            return Err(Cause::InvalidFree(address));
+        }
+
+        let old = &self.block(address, address)?.bytes;
+        let len = old.len().min(size);
+
+        let mut bytes = zeroed(size)?;
+        bytes[..len].copy_from_slice(&old[..len]);
+
+        // the old block is only freed once the new one exists, a failed realloc leaves it alone
+        let new = self.insert(bytes, site)?;
+        self.free(address, site)?;
+
+        Ok(new)
+    }
+
+    pub fn read(&self, start: usize, end: usize) -> Result<&[u8], Cause> {
+        let base = self.block_start(start, end)?;
+
+        self.block(base, start)?
+            .bytes
+            .get(start - base..end - base)
+            .ok_or(Cause::OutOfBounds { start, end })
+    }
+
+    // the bytes from `start` up to the next nul in the same block
+    pub fn read_str(&self, start: usize) -> Result<&[u8], Cause> {
+        let base = self.block_start(start, start)?;
+        let bytes = &self.block(base, start)?.bytes;
+        let end = base + bytes.len();
+
+        let bytes = bytes
+            .get(start - base..)
+            .ok_or(Cause::OutOfBounds { start, end })?;
+
+        let len = bytes
+            .iter()
+            .position(|i| *i == 0)
+            .ok_or(Cause::OutOfBounds { start, end })?;
+
+        Ok(&bytes[..len])
+    }
+
+    pub fn write(&mut self, start: usize, end: usize) -> Result<&mut [u8], Cause> {
+        let base = self.block_start(start, end)?;
+        self.block(base, start)?;
+
+        self.blocks
+            .get_mut(&base)
+            .expect("the block was found above")
+            .bytes
+            .get_mut(start - base..end - base)
+            .ok_or(Cause::OutOfBounds { start, end })
+    }
+
+    pub fn leaks(&self) -> Vec<Leak> {
+        self.blocks
+            .iter()
+            .filter(|(_, block)| block.freed.is_none())
+            .map(|(address, block)| Leak {
+                address: *address,
+                size: block.bytes.len(),
+                site: block.site,
+            })
+            .collect()
+    }
+
+    fn insert(&mut self, bytes: Vec<u8>, site: usize) -> Result<usize, Cause> {
+        let size = bytes.len();
+        let address = self.next;
+
+        // keep a gap of at least a byte so that an empty block still has its own address
+        self.next = size
+            .max(1)
+            .checked_next_multiple_of(8)
+            .and_then(|i| address.checked_add(i))
+            .ok_or(Cause::OutOfMemory(size))?;
+
+        self.live += size;
+        self.blocks.insert(
+            address,
+            Block {
+                bytes,
+                site,
+                freed: None,
+            },
+        );
+
+        Ok(address)
+    }
+
+    // the address of the block that `start` points into
+    fn block_start(&self, start: usize, end: usize) -> Result<usize, Cause> {
+        self.blocks
+            .range(..=start)
+            .next_back()
+            .map(|(address, _)| *address)
+            .ok_or(Cause::OutOfBounds { start, end })
+    }
+
+    fn block(&self, base: usize, address: usize) -> Result<&Block, Cause> {
+        let block = self.blocks.get(&base).ok_or(Cause::OutOfBounds {
+            start: address,
+            end: address,
+        })?;
+
+        if let Some(freed) = block.freed {
+            return Err(Cause::UseAfterFree {
+                address,
+                allocated: block.site,
+                freed,
+            });
+        }
+
+        Ok(block)
+    }
+}
+
+// the host running out of memory is reported like the program going over its limit
+fn zeroed(size: usize) -> Result<Vec<u8>, Cause> {
+    let mut bytes = Vec::new();
+    bytes
+        .try_reserve_exact(size)
+        .map_err(|_| Cause::OutOfMemory(size))?;
+    bytes.resize(size, 0);
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vm;
+
+    fn run(source: &str) -> Result<Vm, crate::RuntimeError> {
+        let mut vm = Vm::new(crate::compile(source).unwrap()).unwrap();
+        vm.run()?;
+        Ok(vm)
+    }
+
+    #[test]
+    fn reports_where_a_block_was_allocated_and_freed() {
+        let error = run("load 8 d0\nalloc d0 d1\nfree d1\nloadp d1 c0").unwrap_err();
+        assert_eq!(
+            error.cause,
+            Cause::UseAfterFree {
+                address: HEAP_BASE,
+                allocated: 1,
+                freed: 2
+            }
+        );
+
+        let error = run("load 8 d0\nalloc d0 d1\nfree d1\nfree d1").unwrap_err();
+        assert_eq!(
+            error.cause,
+            Cause::DoubleFree {
+                address: HEAP_BASE,
+                allocated: 1,
+                freed: 2
+            }
+        );
+        assert_eq!(error.pc, 3);
+    }
+
+    #[test]
+    fn keeps_the_contents_of_a_reallocated_block() {
+        let vm = run("push uint64 out
+load 8 d0
+alloc d0 d1
+load 42 d2
+storep d2 d1
+load 64 d0
+realloc d0 d1
+loadp d1 d3
+unload d3 out")
+        .unwrap();
+
+        assert_eq!(vm.get::<u64>("out"), Ok(42));
+        assert_eq!(vm.leaks().len(), 1);
+        assert_eq!(vm.leaks()[0].size, 64);
+        assert_eq!(vm.leaks()[0].site, 6);
+    }
+
+    #[test]
+    fn reports_allocations_the_host_cannot_make() {
+        let mut vm =
+            Vm::new(crate::compile("load 18446744073709551615 d0\nalloc d0 d1").unwrap()).unwrap();
+        vm.set_memory_limit(usize::MAX);
+
+        assert_eq!(vm.run().unwrap_err().cause, Cause::OutOfMemory(usize::MAX));
+
+        let mut heap = Heap::new();
+        heap.next = usize::MAX - 4;
+        assert_eq!(heap.alloc(1, 0), Err(Cause::OutOfMemory(1)));
+    }
+
+    #[test]
+    fn forgets_the_oldest_freed_blocks() {
+        let mut heap = Heap::new();
+        let first = heap.alloc(1, 0).unwrap();
+        heap.free(first, 0).unwrap();
+
+        for _ in 0..FREED_HISTORY {
+            let address = heap.alloc(1, 0).unwrap();
+            heap.free(address, 0).unwrap();
+        }
+
+        assert_eq!(heap.blocks.len(), FREED_HISTORY);
+        assert_eq!(heap.free(first, 0), Err(Cause::InvalidFree(first)));
+    }
+}
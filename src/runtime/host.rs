@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use super::{Memory, Register};
+use crate::alias::Str;
+use crate::error::Cause;
+
+// by convention arguments are passed in d0-d3 (f0-f3 or g0-g3 for floats) and
+// the result is returned in d0 (or g0), strings are passed as addresses in memory
+pub type HostFunction = Box<dyn FnMut(&mut Register, &mut Memory) -> Result<(), Str>>;
+
+// the functions a program can call with `extern`, checked when the vm is linked
+#[derive(Default)]
+pub struct Host {
+    functions: HashMap<Str, HostFunction>,
+}
+
+impl Host {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // replaces any function already registered under `name`
+    pub fn register<F>(&mut self, name: &str, function: F)
+    where
+        F: FnMut(&mut Register, &mut Memory) -> Result<(), Str> + 'static,
+    {
+        self.functions.insert(name.into(), Box::new(function));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    pub fn call(
+        &mut self,
+        name: &str,
+        register: &mut Register,
+        memory: &mut Memory,
+    ) -> Result<(), Cause> {
+        let function = self
+            .functions
+            .get_mut(name)
+            .ok_or_else(|| Cause::UnknownHostFunction(name.into()))?;
+
+        function(register, memory).map_err(Cause::HostFunction)
+    }
+}
+
+impl std::fmt::Debug for Host {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.functions.keys()).finish()
+    }
+}
@@ -1,24 +1,52 @@
-use crate::alias::{Name, Str};
+use crate::alias::Str;
 
 use super::{
-    operation::{BinOperator, UnOperator},
-    RegisterName, Type, Value,
+    operation::{BinOperator, Conversion, UnOperator},
+    Element, Flag, Label, Operand, RegisterName, Type, Value, Variable,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Instruction {
-    Push(Type, Name),
-    Set(Name, Value),
+    Push(Type, Variable),
+    Set(Variable, Value),
 
     Load(Value, RegisterName),
-    Unload(RegisterName, Name),
+    Unload(RegisterName, Variable),
+    LoadElement(Element, RegisterName),
+    UnloadElement(RegisterName, Element),
     Move(RegisterName, RegisterName),
 
-    Call(Str),
-    CallIf(Str, RegisterName),
-    Jump(Str),
-    JumpIf(Str, RegisterName),
+    // the address goes in, or is read from, a d register
+    AddressOf(Variable, RegisterName),
+    LoadPointer(RegisterName, RegisterName),
+    StorePointer(RegisterName, RegisterName),
 
-    BinaryOp(BinOperator, RegisterName, RegisterName, RegisterName),
+    // the size, then the register that receives or holds the address
+    Alloc(RegisterName, RegisterName),
+    Free(RegisterName),
+    Realloc(RegisterName, RegisterName),
+
+    // registers are printed and read as the given type, which has the size of the register
+    Print(Value),
+    PrintRegister(RegisterName, Type),
+    PrintString(RegisterName),
+    Read(Variable),
+    ReadRegister(RegisterName, Type),
+    ReadLine(Variable),
+
+    Convert(Conversion, RegisterName, RegisterName),
+
+    // the index of its `end`, and the size of its frame
+    Function(usize, usize),
+    Return,
+    Call(Label),
+    Extern(Str),
+    CallIf(Label, RegisterName),
+    Jump(Label),
+    JumpIf(Label, RegisterName),
+    JumpFlag(Label, Flag),
+    Exit(Option<RegisterName>),
+
+    BinaryOp(BinOperator, Operand, Operand, RegisterName),
     UnaryOp(UnOperator, RegisterName, RegisterName),
 }
@@ -0,0 +1,100 @@
+use std::io::{BufRead, Write};
+
+use super::{find_var, find_var_idx, load_reg, set_var, Frame, Memory, Register, RegisterName};
+use super::{Type, Value, Variable};
+use crate::error::Cause;
+use crate::lexer::Literal;
+
+pub fn print_value(value: &Value, frames: &[Frame], memory: &Memory) -> Result<(), Cause> {
+    match value {
+        Value::RValue(literal) => match literal {
+            Literal::Boolean(i) => print!("{}", i),
+            Literal::Float(i) => print!("{}", i),
+            Literal::Integer(i, _) => print!("{}", i),
+            Literal::Character(i) => print!("{}", i),
+            Literal::String(i) => print!("{}", i),
+        },
+        Value::LValue(var) => {
+            let (start, var_type) = find_var(var, frames)?;
+            let bytes = memory.read(start, start + var_type.size())?;
+
+            print!("{}", var_type.format(bytes));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn print_register(reg: RegisterName, value_type: &Type, register: &Register) {
+    print!("{}", value_type.format(&register.to_bytes(reg)));
+}
+
+// prints the nul terminated string whose address is in `reg`
+pub fn print_string(reg: RegisterName, register: &Register, memory: &Memory) -> Result<(), Cause> {
+    let bytes = memory.read_str(register.d[reg.index()] as usize)?;
+    print!("{}", String::from_utf8_lossy(bytes));
+
+    Ok(())
+}
+
+pub fn read_variable(var: &Variable, frames: &[Frame], memory: &mut Memory) -> Result<(), Cause> {
+    let (_, var_type) = find_var(var, frames)?;
+    let literal = var_type.parse(&input()?).map_err(Cause::InvalidInput)?;
+
+    set_var(var, &Value::RValue(literal), frames, memory)
+}
+
+pub fn read_register(
+    reg: RegisterName,
+    value_type: &Type,
+    frames: &[Frame],
+    memory: &Memory,
+    register: &mut Register,
+) -> Result<(), Cause> {
+    let literal = value_type.parse(&input()?).map_err(Cause::InvalidInput)?;
+
+    load_reg(&Value::RValue(literal), &reg, frames, memory, register)
+}
+
+// stores as much of the line as fits in the variable, always leaving room for a nul
+pub fn read_line(var: &Variable, frames: &[Frame], memory: &mut Memory) -> Result<(), Cause> {
+    let (start, end) = find_var_idx(var, frames)?;
+
+    if end == start {
+        return Err(Cause::InvalidInput(
+            "cannot read a line into a variable without room for a nul".into(),
+        ));
+    }
+
+    let line = input()?;
+
+    let mut len = line.len().min(end - start - 1);
+    while !line.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    let bytes = memory.write(start, end)?;
+    bytes.fill(0);
+    bytes[..len].copy_from_slice(&line.as_bytes()[..len]);
+
+    Ok(())
+}
+
+// the next line of stdin without its line ending
+fn input() -> Result<String, Cause> {
+    std::io::stdout()
+        .flush()
+        .map_err(|e| Cause::InvalidInput(e.to_string().into()))?;
+
+    let mut line = String::new();
+    match std::io::stdin().lock().read_line(&mut line) {
+        Ok(0) => return Err(Cause::InvalidInput("end of input".into())),
+        Ok(_) => {}
+        Err(e) => return Err(Cause::InvalidInput(e.to_string().into())),
+    }
+
+    let len = line.trim_end_matches(['\n', '\r']).len();
+    line.truncate(len);
+
+    Ok(line)
+}
@@ -0,0 +1,9 @@
+use crate::alias::Str;
+use crate::lexer::Span;
+
+// the parser only knows the name, `parser::link` replaces it with the index it labels
+#[derive(Debug, PartialEq, Clone)]
+pub enum Label {
+    Name(Str, Span),
+    Target(usize),
+}
@@ -0,0 +1,121 @@
+use crate::error::Cause;
+
+use super::heap::{Heap, Leak, HEAP_BASE};
+
+pub const DEFAULT_MEMORY_LIMIT: usize = 1024 * 1024;
+
+#[derive(Debug)]
+pub struct Memory {
+    bytes: Vec<u8>,
+    limit: usize,
+    // everything below this address is the read-only data segment
+    read_only: usize,
+    heap: Heap,
+}
+
+impl Memory {
+    pub fn new(limit: usize, data: Vec<u8>) -> Self {
+        Self {
+            read_only: data.len(),
+            bytes: data,
+            limit,
+            heap: Heap::new(),
+        }
+    }
+
+    // memory that is already reserved is kept even if it is above the new limit
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    // grows the memory so that everything below `end` is addressable,
+    // the stack and the heap share the limit
+    pub fn reserve(&mut self, end: usize) -> Result<(), Cause> {
+        if end.saturating_add(self.heap.live()) > self.limit {
+            return Err(Cause::StackOverflow);
+        }
+
+        if end > self.bytes.len() {
+            self.bytes.resize(end, 0);
+        }
+
+        Ok(())
+    }
+
+    pub fn alloc(&mut self, size: usize, site: usize) -> Result<usize, Cause> {
+        self.check_heap(size)?;
+        self.heap.alloc(size, site)
+    }
+
+    pub fn free(&mut self, address: usize, site: usize) -> Result<(), Cause> {
+        self.heap.free(address, site)
+    }
+
+    pub fn realloc(&mut self, address: usize, size: usize, site: usize) -> Result<usize, Cause> {
+        self.check_heap(size)?;
+        self.heap.realloc(address, size, site)
+    }
+
+    pub fn leaks(&self) -> Vec<Leak> {
+        self.heap.leaks()
+    }
+
+    pub fn read(&self, start: usize, end: usize) -> Result<&[u8], Cause> {
+        if start >= HEAP_BASE {
+            return self.heap.read(start, end);
+        }
+
+        self.bytes
+            .get(start..end)
+            .ok_or(Cause::OutOfBounds { start, end })
+    }
+
+    // the bytes from `start` up to the next nul
+    pub fn read_str(&self, start: usize) -> Result<&[u8], Cause> {
+        if start >= HEAP_BASE {
+            return self.heap.read_str(start);
+        }
+
+        let end = self.bytes.len();
+        let bytes = self
+            .bytes
+            .get(start..)
+            .ok_or(Cause::OutOfBounds { start, end })?;
+
+        let len = bytes
+            .iter()
+            .position(|i| *i == 0)
+            .ok_or(Cause::OutOfBounds { start, end })?;
+
+        Ok(&bytes[..len])
+    }
+
+    pub fn write(&mut self, start: usize, end: usize) -> Result<&mut [u8], Cause> {
+        if start >= HEAP_BASE {
+            return self.heap.write(start, end);
+        }
+
+        if start < self.read_only {
+            return Err(Cause::ReadOnly(start));
+        }
+
+        self.bytes
+            .get_mut(start..end)
+            .ok_or(Cause::OutOfBounds { start, end })
+    }
+
+    // the old block is still counted while it is reallocated, which errs on the safe side
+    fn check_heap(&self, size: usize) -> Result<(), Cause> {
+        let used = self.bytes.len() + self.heap.live();
+
+        if used.saturating_add(size) > self.limit {
+            return Err(Cause::OutOfMemory(size));
+        }
+
+        Ok(())
+    }
+}
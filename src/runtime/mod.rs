@@ -1,303 +1,627 @@
-use std::collections::HashMap;
+use crate::error::Cause;
 
-use crate::alias::{Name, Str};
+mod frame;
+use frame::Frame;
+
+mod io;
+
+mod host;
+pub use host::{Host, HostFunction};
+
+mod memory;
+pub use memory::{Memory, DEFAULT_MEMORY_LIMIT};
+
+mod heap;
+pub use heap::{Leak, HEAP_BASE};
 
 mod instruction;
 pub use instruction::Instruction;
 
 mod types;
-pub use types::Type;
+pub use types::{Field, Struct, Type, MAX_TYPE_SIZE};
 
 mod register;
-pub use register::{Register, RegisterName};
+pub use register::{Flag, Register, RegisterClass, RegisterName};
+
+mod arithmetic;
 
 mod operation;
-pub use operation::{BinOperator, UnOperator};
+pub use operation::{BinOperator, Conversion, UnOperator};
 
 mod value;
 pub use value::Value;
 
-pub fn run(instruction_set: Vec<Instruction>, labels: HashMap<Str, usize>) {
-    let mut variables: Vec<(Name, Type, usize)> = Vec::new();
+mod operand;
+pub use operand::Operand;
 
-    let mut register = Register::default();
-    let mut stack = [0u8; 16];
+mod variable;
+pub use variable::{Element, Scope, Slot, Variable};
 
-    let mut pc = 0;
+mod label;
+pub use label::Label;
 
-    while pc < instruction_set.len() {
-        execute(
-            &instruction_set[pc],
-            &mut variables,
-            &mut register,
-            &mut stack,
-            &labels,
-            &mut pc,
-        );
-        pc += 1;
-    }
+mod program;
+pub use program::Program;
 
-    println!("stack: {:?}", stack);
-    println!("register: {:#?}", register);
-}
+mod primitive;
+pub use primitive::Primitive;
+
+mod vm;
+pub use vm::{State, Vm};
+
+const MAX_CALL_DEPTH: usize = 1 << 16;
 
 fn execute(
-    instruction: &Instruction,
-    variables: &mut Vec<(Name, Type, usize)>,
+    instructions: &[Instruction],
+    frames: &mut Vec<Frame>,
     register: &mut Register,
-    stack: &mut [u8],
-    labels: &HashMap<Str, usize>,
+    memory: &mut Memory,
+    host: &mut Host,
     pc: &mut usize,
-) {
+) -> Result<Option<i32>, Cause> {
+    let instruction = &instructions[*pc];
+    let site = *pc;
+
+    // jumps overwrite the pc, so it already points at the next instruction
+    *pc += 1;
+
     match instruction {
-        Instruction::Push(var_type, name) => push_var(var_type.clone(), name.clone(), variables),
-        Instruction::Set(var_name, value) => set_var(var_name.clone(), value, variables, stack),
+        Instruction::Push(_, var) => push_var(var, frames, memory)?,
+        Instruction::Set(var, value) => set_var(var, value, frames, memory)?,
         Instruction::Load(value, register_name) => {
-            load_reg(value, register_name, variables, stack, register)
+            load_reg(value, register_name, frames, memory, register)?
+        }
+        Instruction::Unload(reg, var) => unload_reg(reg, var, frames, memory, register)?,
+        Instruction::LoadElement(element, reg) => {
+            let (start, end) = find_element(element, frames, register)?;
+            let bytes = memory.read(start, end)?.to_vec();
+
+            write_reg(&bytes, reg, register)?
         }
-        Instruction::Unload(reg, var_name) => {
-            unload_reg(reg, var_name, variables, stack, register).unwrap()
+        Instruction::UnloadElement(reg, element) => {
+            let (start, end) = find_element(element, frames, register)?;
+            unload_range(reg, start, end, memory, register)?
+        }
+        Instruction::Move(src, dst) => register.copy(*src, *dst)?,
+        Instruction::AddressOf(var, dst) => {
+            let (start, _) = find_var(var, frames)?;
+            register.d[dst.index()] = start as u64;
+        }
+        Instruction::Alloc(size, dst) => {
+            register.d[dst.index()] = memory.alloc(register.d[size.index()] as usize, site)? as u64
+        }
+        Instruction::Free(ptr) => memory.free(register.d[ptr.index()] as usize, site)?,
+        Instruction::Realloc(size, ptr) => {
+            let address = register.d[ptr.index()] as usize;
+            let size = register.d[size.index()] as usize;
+
+            register.d[ptr.index()] = memory.realloc(address, size, site)? as u64
         }
-        Instruction::Move(_, _) => todo!(),
-        Instruction::Call(_) => todo!(),
-        Instruction::CallIf(_, _) => todo!(),
-        Instruction::Jump(label) => *pc = labels[label],
+        Instruction::LoadPointer(ptr, dst) => {
+            let start = register.d[ptr.index()] as usize;
+            let bytes = memory
+                .read(start, start.saturating_add(dst.size()))?
+                .to_vec();
+
+            write_reg(&bytes, dst, register)?
+        }
+        Instruction::StorePointer(src, ptr) => {
+            let start = register.d[ptr.index()] as usize;
+
+            memory
+                .write(start, start.saturating_add(src.size()))?
+                .copy_from_slice(&register.to_bytes(*src));
+        }
+        Instruction::Convert(conversion, src, dst) => register.convert(*conversion, *src, *dst)?,
+        Instruction::Function(body_end, _) => *pc = *body_end + 1,
+        Instruction::Return => {
+            if frames.len() == 1 {
+                return Err(Cause::ReturnOutsideFunction);
+            }
+
+            *pc = frames.pop().expect("frames cannot be empty").return_addr;
+        }
+        Instruction::Call(label) => call(label, instructions, frames, memory, pc)?,
+        Instruction::Extern(name) => host.call(name, register, memory)?,
+        Instruction::CallIf(label, reg) => {
+            if register.i[reg.index()] {
+                call(label, instructions, frames, memory, pc)?;
+            }
+        }
+        Instruction::Jump(label) => *pc = find_label(label)?,
         Instruction::JumpIf(label, reg) => {
             if register.i[reg.index()] {
-                *pc = labels[label];
+                *pc = find_label(label)?;
             }
         }
+        Instruction::Print(value) => io::print_value(value, frames, memory)?,
+        Instruction::PrintRegister(reg, value_type) => {
+            io::print_register(*reg, value_type, register)
+        }
+        Instruction::PrintString(reg) => io::print_string(*reg, register, memory)?,
+        Instruction::Read(var) => io::read_variable(var, frames, memory)?,
+        Instruction::ReadRegister(reg, value_type) => {
+            io::read_register(*reg, value_type, frames, memory, register)?
+        }
+        Instruction::ReadLine(var) => io::read_line(var, frames, memory)?,
 
-        Instruction::BinaryOp(op, src1, src2, dst) => {
-            register.bin_operate(*op, *src1, *src2, *dst).unwrap()
+        Instruction::JumpFlag(label, flag) => {
+            if register.flags.get(*flag) {
+                *pc = find_label(label)?;
+            }
         }
-        Instruction::UnaryOp(op, src, dst) => register.un_operate(*op, *src, *dst).unwrap(),
+
+        Instruction::BinaryOp(op, lhs, rhs, dst) => register.bin_operate(*op, *lhs, *rhs, *dst)?,
+        Instruction::UnaryOp(op, src, dst) => register.un_operate(*op, *src, *dst)?,
+
+        // the status is truncated the same way the OS truncates it
+        Instruction::Exit(reg) => return Ok(Some(reg.map_or(0, |i| register.read_int(i) as i32))),
+    }
+
+    Ok(None)
+}
+
+fn call(
+    label: &Label,
+    instructions: &[Instruction],
+    frames: &mut Vec<Frame>,
+    memory: &mut Memory,
+    pc: &mut usize,
+) -> Result<(), Cause> {
+    let target = find_label(label)?;
+
+    // calling a function enters its body, the link pass only lets calls reach
+    // functions but hand built programs may call any index and get an empty frame
+    let (target, size) = match instructions.get(target) {
+        Some(Instruction::Function(_, size)) => (target + 1, *size),
+        _ => (target, 0),
+    };
+
+    // frames without locals take no memory, so the depth is bounded as well
+    if frames.len() >= MAX_CALL_DEPTH {
+        return Err(Cause::StackOverflow);
+    }
+
+    let base = frames.last().expect("frames cannot be empty").top();
+    memory.reserve(base.saturating_add(size))?;
+    frames.push(Frame::new(*pc, base, size));
+
+    *pc = target;
+    Ok(())
+}
+
+fn find_label(label: &Label) -> Result<usize, Cause> {
+    match label {
+        Label::Target(i) => Ok(*i),
+        Label::Name(name, _) => Err(Cause::UnknownLabel(name.clone())),
     }
 }
 
-fn push_var(var_type: Type, name: Name, variables: &mut Vec<(Name, Type, usize)>) {
-    let idx = variables
-        .last()
-        .map(|(_, prev_type, prev_idx)| prev_idx + prev_type.size())
-        .unwrap_or(0);
+// locals start out zeroed every time their `push` runs, globals are zeroed once
+// when the vm is made so that an embedder can set them before the program runs
+fn push_var(var: &Variable, frames: &[Frame], memory: &mut Memory) -> Result<(), Cause> {
+    let (start, end) = find_var_idx(var, frames)?;
+
+    memory.reserve(end)?;
+
+    if let Variable::Slot(Slot {
+        scope: Scope::Local,
+        ..
+    }) = var
+    {
+        memory.write(start, end)?.fill(0);
+    }
 
-    variables.push((name, var_type, idx))
+    Ok(())
 }
 
-fn set_var(var_name: Name, value: &Value, variables: &[(Name, Type, usize)], stack: &mut [u8]) {
-    let (start, end) = find_var_idx(&var_name, variables);
+fn set_var(
+    var: &Variable,
+    value: &Value,
+    frames: &[Frame],
+    memory: &mut Memory,
+) -> Result<(), Cause> {
+    let (start, end) = find_var_idx(var, frames)?;
 
-    match value {
-        Value::RValue(literal) => stack[start..end].copy_from_slice(&literal.as_bytes(end - start)),
+    let bytes = match value {
+        Value::RValue(literal) => literal
+            .as_bytes(end - start)
+            .map_err(Cause::InvalidLiteral)?,
         Value::LValue(var) => {
-            let (start_clone, end_clone) = find_var_idx(var, variables);
-
-            for (i, j) in (start..end).zip(start_clone..end_clone) {
-                stack[i] = stack[j];
-            }
+            let (start_clone, end_clone) = find_var_idx(var, frames)?;
+            memory.read(start_clone, end_clone)?.to_vec()
         }
     };
+
+    if bytes.len() != end - start {
+        return Err(Cause::SizeMismatch {
+            expected: end - start,
+            found: bytes.len(),
+        });
+    }
+
+    memory.write(start, end)?.copy_from_slice(&bytes);
+
+    Ok(())
 }
 
-fn find_var_idx(var_name: &str, variables: &[(Name, Type, usize)]) -> (usize, usize) {
-    variables
-        .iter()
-        .find_map(|(name, var_type, idx)| {
-            if *var_name == **name {
-                Some((*idx, idx + var_type.size()))
-            } else {
-                None
-            }
-        })
-        .unwrap()
+// the address and type of a variable, locals are relative to the current frame
+fn find_var<'a>(var: &'a Variable, frames: &[Frame]) -> Result<(usize, &'a Type), Cause> {
+    let slot = match var {
+        Variable::Slot(i) => i,
+        Variable::Name(name, ..) => return Err(Cause::UnknownVariable(name.clone())),
+    };
+
+    let frame = match slot.scope {
+        Scope::Global => &frames[0],
+        Scope::Local => frames.last().expect("frames cannot be empty"),
+    };
+
+    Ok((frame.base + slot.offset, &slot.var_type))
+}
+
+fn find_var_idx(var: &Variable, frames: &[Frame]) -> Result<(usize, usize), Cause> {
+    let (start, var_type) = find_var(var, frames)?;
+    Ok((start, start + var_type.size()))
+}
+
+// the address range of an array element, the index is checked against the array's length
+fn find_element(
+    element: &Element,
+    frames: &[Frame],
+    register: &Register,
+) -> Result<(usize, usize), Cause> {
+    let (start, var_type) = find_var(&element.array, frames)?;
+
+    let Type::Array(element_type, len) = var_type else {
+        return Err(Cause::NotAnArray(element.name.clone()));
+    };
+
+    let index = match element.index {
+        Operand::Register(i) => register.read_int(i),
+        Operand::Immediate(i) => i,
+    };
+
+    if index >= *len as u64 {
+        return Err(Cause::IndexOutOfBounds {
+            array: element.name.clone(),
+            index,
+            len: *len,
+        });
+    }
+
+    let start = start + index as usize * element_type.size();
+    Ok((start, start + element_type.size()))
 }
 
 fn load_reg(
     value: &Value,
     reg_name: &RegisterName,
-    variables: &[(Name, Type, usize)],
-    stack: &mut [u8],
+    frames: &[Frame],
+    memory: &Memory,
     register: &mut Register,
-) {
+) -> Result<(), Cause> {
     let bytes = match value {
-        Value::RValue(i) => i.as_bytes(reg_name.size()),
+        Value::RValue(i) => i.as_bytes(reg_name.size()).map_err(Cause::InvalidLiteral)?,
         Value::LValue(i) => {
-            let (start, end) = find_var_idx(i, variables);
-            stack[start..end].to_vec()
+            let (start, end) = find_var_idx(i, frames)?;
+            memory.read(start, end)?.to_vec()
         }
     };
 
+    write_reg(&bytes, reg_name, register)
+}
+
+fn write_reg(bytes: &[u8], reg_name: &RegisterName, register: &mut Register) -> Result<(), Cause> {
+    if bytes.len() != reg_name.size() {
+        return Err(Cause::SizeMismatch {
+            expected: reg_name.size(),
+            found: bytes.len(),
+        });
+    }
+
     match reg_name {
         RegisterName::A0 => register.a[0] = bytes[0],
         RegisterName::A1 => register.a[1] = bytes[0],
         RegisterName::A2 => register.a[2] = bytes[0],
         RegisterName::A3 => register.a[3] = bytes[0],
 
-        RegisterName::B0 => register.b[0] = u16::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::B1 => register.b[1] = u16::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::B2 => register.b[2] = u16::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::B3 => register.b[3] = u16::from_ne_bytes(bytes.try_into().unwrap()),
+        RegisterName::B0 => {
+            register.b[0] = u16::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::B1 => {
+            register.b[1] = u16::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::B2 => {
+            register.b[2] = u16::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::B3 => {
+            register.b[3] = u16::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
 
-        RegisterName::C0 => register.c[0] = u32::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::C1 => register.c[1] = u32::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::C2 => register.c[2] = u32::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::C3 => register.c[3] = u32::from_ne_bytes(bytes.try_into().unwrap()),
+        RegisterName::C0 => {
+            register.c[0] = u32::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::C1 => {
+            register.c[1] = u32::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::C2 => {
+            register.c[2] = u32::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::C3 => {
+            register.c[3] = u32::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
 
-        RegisterName::D0 => register.d[0] = u64::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::D1 => register.d[1] = u64::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::D2 => register.d[2] = u64::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::D3 => register.d[3] = u64::from_ne_bytes(bytes.try_into().unwrap()),
+        RegisterName::D0 => {
+            register.d[0] = u64::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::D1 => {
+            register.d[1] = u64::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::D2 => {
+            register.d[2] = u64::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::D3 => {
+            register.d[3] = u64::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
 
-        RegisterName::F0 => register.f[0] = f32::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::F1 => register.f[1] = f32::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::F2 => register.f[2] = f32::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::F3 => register.f[3] = f32::from_ne_bytes(bytes.try_into().unwrap()),
+        RegisterName::F0 => {
+            register.f[0] = f32::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::F1 => {
+            register.f[1] = f32::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::F2 => {
+            register.f[2] = f32::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::F3 => {
+            register.f[3] = f32::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
 
-        RegisterName::G0 => register.g[0] = f64::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::G1 => register.g[1] = f64::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::G2 => register.g[2] = f64::from_ne_bytes(bytes.try_into().unwrap()),
-        RegisterName::G3 => register.g[3] = f64::from_ne_bytes(bytes.try_into().unwrap()),
+        RegisterName::G0 => {
+            register.g[0] = f64::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::G1 => {
+            register.g[1] = f64::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::G2 => {
+            register.g[2] = f64::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
+        RegisterName::G3 => {
+            register.g[3] = f64::from_ne_bytes(bytes.try_into().expect("size is checked above"))
+        }
 
         RegisterName::I0 => register.i[0] = bytes[0] != 0,
         RegisterName::I1 => register.i[1] = bytes[0] != 0,
         RegisterName::I2 => register.i[2] = bytes[0] != 0,
-        RegisterName::I3 => register.i[2] = bytes[0] != 0,
+        RegisterName::I3 => register.i[3] = bytes[0] != 0,
     }
+
+    Ok(())
 }
 
 fn unload_reg(
     reg: &RegisterName,
-    var_name: &str,
-    variables: &[(Name, Type, usize)],
-    stack: &mut [u8],
+    var: &Variable,
+    frames: &[Frame],
+    memory: &mut Memory,
     register: &mut Register,
-) -> Result<(), Str> {
-    let (start, end) = find_var_idx(var_name, variables);
+) -> Result<(), Cause> {
+    let (start, end) = find_var_idx(var, frames)?;
+    unload_range(reg, start, end, memory, register)
+}
 
+fn unload_range(
+    reg: &RegisterName,
+    start: usize,
+    end: usize,
+    memory: &mut Memory,
+    register: &mut Register,
+) -> Result<(), Cause> {
     if end - start != reg.size() {
-        return Err("Not of same size".into());
+        return Err(Cause::SizeMismatch {
+            expected: end - start,
+            found: reg.size(),
+        });
     }
 
+    let stack = memory.write(start, end)?;
+
     match reg {
         RegisterName::A0 => {
-            stack[start] = register.a[0];
+            stack[0] = register.a[0];
             register.a[0] = 0;
         }
         RegisterName::A1 => {
-            stack[start] = register.a[1];
-            register.a[0] = 0;
+            stack[0] = register.a[1];
+            register.a[1] = 0;
         }
         RegisterName::A2 => {
-            stack[start] = register.a[2];
-            register.a[0] = 0;
+            stack[0] = register.a[2];
+            register.a[2] = 0;
         }
         RegisterName::A3 => {
-            stack[start] = register.a[3];
-            register.a[0] = 0;
+            stack[0] = register.a[3];
+            register.a[3] = 0;
         }
 
         RegisterName::B0 => {
-            stack[start..end].copy_from_slice(&register.b[0].to_ne_bytes());
+            stack.copy_from_slice(&register.b[0].to_ne_bytes());
             register.b[0] = 0;
         }
         RegisterName::B1 => {
-            stack[start..end].copy_from_slice(&register.b[1].to_ne_bytes());
+            stack.copy_from_slice(&register.b[1].to_ne_bytes());
             register.b[1] = 0;
         }
         RegisterName::B2 => {
-            stack[start..end].copy_from_slice(&register.b[2].to_ne_bytes());
+            stack.copy_from_slice(&register.b[2].to_ne_bytes());
             register.b[2] = 0;
         }
         RegisterName::B3 => {
-            stack[start..end].copy_from_slice(&register.b[3].to_ne_bytes());
+            stack.copy_from_slice(&register.b[3].to_ne_bytes());
             register.b[3] = 0;
         }
 
         RegisterName::C0 => {
-            stack[start..end].copy_from_slice(&register.c[0].to_ne_bytes());
+            stack.copy_from_slice(&register.c[0].to_ne_bytes());
             register.c[0] = 0;
         }
         RegisterName::C1 => {
-            stack[start..end].copy_from_slice(&register.c[1].to_ne_bytes());
+            stack.copy_from_slice(&register.c[1].to_ne_bytes());
             register.c[1] = 0;
         }
         RegisterName::C2 => {
-            stack[start..end].copy_from_slice(&register.c[2].to_ne_bytes());
+            stack.copy_from_slice(&register.c[2].to_ne_bytes());
             register.c[2] = 0;
         }
         RegisterName::C3 => {
-            stack[start..end].copy_from_slice(&register.c[3].to_ne_bytes());
+            stack.copy_from_slice(&register.c[3].to_ne_bytes());
             register.c[3] = 0;
         }
 
         RegisterName::D0 => {
-            stack[start..end].copy_from_slice(&register.d[0].to_ne_bytes());
+            stack.copy_from_slice(&register.d[0].to_ne_bytes());
             register.d[0] = 0;
         }
         RegisterName::D1 => {
-            stack[start..end].copy_from_slice(&register.d[1].to_ne_bytes());
+            stack.copy_from_slice(&register.d[1].to_ne_bytes());
             register.d[1] = 0;
         }
         RegisterName::D2 => {
-            stack[start..end].copy_from_slice(&register.d[2].to_ne_bytes());
+            stack.copy_from_slice(&register.d[2].to_ne_bytes());
             register.d[2] = 0;
         }
         RegisterName::D3 => {
-            stack[start..end].copy_from_slice(&register.d[3].to_ne_bytes());
+            stack.copy_from_slice(&register.d[3].to_ne_bytes());
             register.d[3] = 0;
         }
 
         RegisterName::F0 => {
-            stack[start..end].copy_from_slice(&register.f[0].to_ne_bytes());
+            stack.copy_from_slice(&register.f[0].to_ne_bytes());
             register.f[0] = 0.0;
         }
         RegisterName::F1 => {
-            stack[start..end].copy_from_slice(&register.f[1].to_ne_bytes());
+            stack.copy_from_slice(&register.f[1].to_ne_bytes());
             register.f[1] = 0.0;
         }
         RegisterName::F2 => {
-            stack[start..end].copy_from_slice(&register.f[2].to_ne_bytes());
+            stack.copy_from_slice(&register.f[2].to_ne_bytes());
             register.f[2] = 0.0;
         }
         RegisterName::F3 => {
-            stack[start..end].copy_from_slice(&register.f[3].to_ne_bytes());
+            stack.copy_from_slice(&register.f[3].to_ne_bytes());
             register.f[3] = 0.0;
         }
 
         RegisterName::G0 => {
-            stack[start..end].copy_from_slice(&register.g[0].to_ne_bytes());
+            stack.copy_from_slice(&register.g[0].to_ne_bytes());
             register.g[0] = 0.0;
         }
         RegisterName::G1 => {
-            stack[start..end].copy_from_slice(&register.g[1].to_ne_bytes());
+            stack.copy_from_slice(&register.g[1].to_ne_bytes());
             register.g[1] = 0.0;
         }
         RegisterName::G2 => {
-            stack[start..end].copy_from_slice(&register.g[2].to_ne_bytes());
+            stack.copy_from_slice(&register.g[2].to_ne_bytes());
             register.g[2] = 0.0;
         }
         RegisterName::G3 => {
-            stack[start..end].copy_from_slice(&register.g[3].to_ne_bytes());
+            stack.copy_from_slice(&register.g[3].to_ne_bytes());
             register.g[3] = 0.0;
         }
 
         RegisterName::I0 => {
-            stack[start] = register.i[0] as u8;
+            stack[0] = register.i[0] as u8;
             register.i[0] = false;
         }
         RegisterName::I1 => {
-            stack[start] = register.i[1] as u8;
+            stack[0] = register.i[1] as u8;
             register.i[1] = false;
         }
         RegisterName::I2 => {
-            stack[start] = register.i[2] as u8;
+            stack[0] = register.i[2] as u8;
             register.i[2] = false;
         }
         RegisterName::I3 => {
-            stack[start] = register.i[3] as u8;
+            stack[0] = register.i[3] as u8;
             register.i[3] = false;
         }
     };
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{Cause, RuntimeError};
+    use crate::Vm;
+
+    fn run(source: &str) -> Result<Vm, RuntimeError> {
+        let mut vm = Vm::new(crate::compile(source).unwrap()).unwrap();
+        vm.run()?;
+        Ok(vm)
+    }
+
+    #[test]
+    fn round_trips_values_through_pointers() {
+        let vm = run("push int32 x
+push int32 y
+push uint64 z
+addr x d0
+load 7 c0
+storep c0 d0
+loadp d0 c1
+unload c1 y
+load 8 d1
+alloc d1 d2
+load 123456789 d3
+storep d3 d2
+loadp d2 d1
+unload d1 z
+free d2")
+        .unwrap();
+
+        assert_eq!(vm.get::<i32>("x"), Ok(7));
+        assert_eq!(vm.get::<i32>("y"), Ok(7));
+        assert_eq!(vm.get::<u64>("z"), Ok(123456789));
+    }
+
+    #[test]
+    fn points_at_fields_and_locals() {
+        let vm = run("struct Pair
+int32 first
+int32 second
+end
+push Pair p
+push int32 out
+fn twice
+push int32 n
+addr n d0
+load 21 c0
+storep c0 d0
+loadp d0 c1
+add c1 c1 c1
+unload c1 out
+end
+addr p.second d0
+load 5 c0
+storep c0 d0
+call twice")
+        .unwrap();
+
+        assert_eq!(vm.get::<i32>("out"), Ok(42));
+        assert_eq!(
+            vm.variable("p").unwrap().1,
+            [0i32.to_ne_bytes(), 5i32.to_ne_bytes()].concat()
+        );
+    }
+
+    #[test]
+    fn rejects_stores_to_string_literals_and_reads_out_of_bounds() {
+        let error = run("load \"abc\" d0\nload 1 a0\nstorep a0 d0").unwrap_err();
+        assert!(matches!(error.cause, Cause::ReadOnly(_)));
+
+        let error = run("load 999999 d0\nloadp d0 c0").unwrap_err();
+        assert!(matches!(error.cause, Cause::OutOfBounds { .. }));
+    }
+}
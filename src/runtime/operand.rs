@@ -0,0 +1,8 @@
+use super::RegisterName;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Operand {
+    Register(RegisterName),
+    // the raw bits of a literal, laid out like a register of the other operand's class
+    Immediate(u64),
+}
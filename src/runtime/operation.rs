@@ -1,3 +1,7 @@
+use crate::lexer::Keyword;
+
+use super::{RegisterClass, RegisterName};
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum BinOperator {
     Add,
@@ -19,6 +23,108 @@ pub enum BinOperator {
     GreaterThan,
     LessThanEqualTo,
     GreaterThanEqualTo,
+
+    // two's complement forms of the operations whose result depends on the sign
+    SignedAdd,
+    SignedSubtract,
+    SignedMultiply,
+    SignedDivide,
+    SignedModulus,
+    ArithmeticRightShift,
+
+    SignedLessThan,
+    SignedGreaterThan,
+    SignedLessThanEqualTo,
+    SignedGreaterThanEqualTo,
+
+    // the plain forms trap on overflow, these wrap around or clamp instead
+    WrappingAdd,
+    WrappingSubtract,
+    WrappingMultiply,
+    WrappingLeftShift,
+
+    SaturatingAdd,
+    SaturatingSubtract,
+    SaturatingMultiply,
+    SaturatingLeftShift,
+
+    // wrap around and take the carry flag as an extra carry in or borrow, the plain `add` and
+    // `sub` trap before they could set it, so a multi-word sum starts with `addw` or `subw`
+    AddCarry,
+    SubtractBorrow,
+}
+
+impl BinOperator {
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinOperator::Equals
+                | BinOperator::NotEquals
+                | BinOperator::LessThan
+                | BinOperator::GreaterThan
+                | BinOperator::LessThanEqualTo
+                | BinOperator::GreaterThanEqualTo
+                | BinOperator::SignedLessThan
+                | BinOperator::SignedGreaterThan
+                | BinOperator::SignedLessThanEqualTo
+                | BinOperator::SignedGreaterThanEqualTo
+        )
+    }
+}
+
+impl TryFrom<Keyword> for BinOperator {
+    type Error = ();
+
+    fn try_from(value: Keyword) -> Result<Self, Self::Error> {
+        match value {
+            Keyword::Add => Ok(Self::Add),
+            Keyword::Subtract => Ok(Self::Subtract),
+            Keyword::Multiply => Ok(Self::Multiply),
+            Keyword::Divide => Ok(Self::Divide),
+            Keyword::Modulus => Ok(Self::Modulus),
+
+            Keyword::LeftShift => Ok(Self::LeftShift),
+            Keyword::RightShift => Ok(Self::RightShift),
+
+            Keyword::And => Ok(Self::And),
+            Keyword::Or => Ok(Self::Or),
+            Keyword::Xor => Ok(Self::Xor),
+
+            Keyword::Equals => Ok(Self::Equals),
+            Keyword::NotEquals => Ok(Self::NotEquals),
+            Keyword::LessThan => Ok(Self::LessThan),
+            Keyword::GreaterThan => Ok(Self::GreaterThan),
+            Keyword::LessThanEqualTo => Ok(Self::LessThanEqualTo),
+            Keyword::GreaterThanEqualTo => Ok(Self::GreaterThanEqualTo),
+
+            Keyword::SignedAdd => Ok(Self::SignedAdd),
+            Keyword::SignedSubtract => Ok(Self::SignedSubtract),
+            Keyword::SignedMultiply => Ok(Self::SignedMultiply),
+            Keyword::SignedDivide => Ok(Self::SignedDivide),
+            Keyword::SignedModulus => Ok(Self::SignedModulus),
+            Keyword::ArithmeticRightShift => Ok(Self::ArithmeticRightShift),
+
+            Keyword::SignedLessThan => Ok(Self::SignedLessThan),
+            Keyword::SignedGreaterThan => Ok(Self::SignedGreaterThan),
+            Keyword::SignedLessThanEqualTo => Ok(Self::SignedLessThanEqualTo),
+            Keyword::SignedGreaterThanEqualTo => Ok(Self::SignedGreaterThanEqualTo),
+
+            Keyword::WrappingAdd => Ok(Self::WrappingAdd),
+            Keyword::WrappingSubtract => Ok(Self::WrappingSubtract),
+            Keyword::WrappingMultiply => Ok(Self::WrappingMultiply),
+            Keyword::WrappingLeftShift => Ok(Self::WrappingLeftShift),
+
+            Keyword::SaturatingAdd => Ok(Self::SaturatingAdd),
+            Keyword::SaturatingSubtract => Ok(Self::SaturatingSubtract),
+            Keyword::SaturatingMultiply => Ok(Self::SaturatingMultiply),
+            Keyword::SaturatingLeftShift => Ok(Self::SaturatingLeftShift),
+
+            Keyword::AddCarry => Ok(Self::AddCarry),
+            Keyword::SubtractBorrow => Ok(Self::SubtractBorrow),
+
+            _ => Err(()),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -26,4 +132,100 @@ pub enum UnOperator {
     Not,
     Increment,
     Decrement,
+
+    SignedIncrement,
+    SignedDecrement,
+
+    WrappingIncrement,
+    WrappingDecrement,
+    SaturatingIncrement,
+    SaturatingDecrement,
+}
+
+impl TryFrom<Keyword> for UnOperator {
+    type Error = ();
+
+    fn try_from(value: Keyword) -> Result<Self, Self::Error> {
+        match value {
+            Keyword::Not => Ok(Self::Not),
+            Keyword::Increment => Ok(Self::Increment),
+            Keyword::Decrement => Ok(Self::Decrement),
+
+            Keyword::SignedIncrement => Ok(Self::SignedIncrement),
+            Keyword::SignedDecrement => Ok(Self::SignedDecrement),
+
+            Keyword::WrappingIncrement => Ok(Self::WrappingIncrement),
+            Keyword::WrappingDecrement => Ok(Self::WrappingDecrement),
+            Keyword::SaturatingIncrement => Ok(Self::SaturatingIncrement),
+            Keyword::SaturatingDecrement => Ok(Self::SaturatingDecrement),
+
+            _ => Err(()),
+        }
+    }
+}
+
+// conversions from one register class to another
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Conversion {
+    ZeroExtend,
+    SignExtend,
+    Truncate,
+
+    IntToFloat,
+    SignedIntToFloat,
+    FloatToInt,
+    FloatToSignedInt,
+    FloatToFloat,
+
+    BoolToInt,
+    IntToBool,
+}
+
+impl TryFrom<Keyword> for Conversion {
+    type Error = ();
+
+    fn try_from(value: Keyword) -> Result<Self, Self::Error> {
+        match value {
+            Keyword::ZeroExtend => Ok(Self::ZeroExtend),
+            Keyword::SignExtend => Ok(Self::SignExtend),
+            Keyword::Truncate => Ok(Self::Truncate),
+
+            Keyword::IntToFloat => Ok(Self::IntToFloat),
+            Keyword::SignedIntToFloat => Ok(Self::SignedIntToFloat),
+            Keyword::FloatToInt => Ok(Self::FloatToInt),
+            Keyword::FloatToSignedInt => Ok(Self::FloatToSignedInt),
+            Keyword::FloatToFloat => Ok(Self::FloatToFloat),
+
+            Keyword::BoolToInt => Ok(Self::BoolToInt),
+            Keyword::IntToBool => Ok(Self::IntToBool),
+
+            _ => Err(()),
+        }
+    }
+}
+
+impl Conversion {
+    // whether the conversion can go from `src` to `dst`, the parser and the vm both check this
+    pub fn accepts(&self, src: RegisterName, dst: RegisterName) -> bool {
+        let (from, to) = (src.register_class(), dst.register_class());
+        let is_float = |i: RegisterClass| matches!(i, RegisterClass::F | RegisterClass::G);
+
+        match self {
+            Conversion::ZeroExtend | Conversion::SignExtend => {
+                from.is_integer() && to.is_integer() && dst.size() >= src.size()
+            }
+            Conversion::Truncate => {
+                from.is_integer() && to.is_integer() && dst.size() <= src.size()
+            }
+            Conversion::IntToFloat | Conversion::SignedIntToFloat => {
+                from.is_integer() && is_float(to)
+            }
+            Conversion::FloatToInt | Conversion::FloatToSignedInt => {
+                is_float(from) && to.is_integer()
+            }
+            Conversion::FloatToFloat => is_float(from) && is_float(to),
+            Conversion::BoolToInt => from == RegisterClass::I && to.is_integer(),
+            Conversion::IntToBool => from.is_integer() && to == RegisterClass::I,
+        }
+    }
 }
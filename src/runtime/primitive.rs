@@ -0,0 +1,65 @@
+use super::Type;
+
+// rust types that can be read from and written to a variable of the matching `Type`
+pub trait Primitive: Sized {
+    const TYPE: Type;
+
+    // `bytes` is always `TYPE.size()` long
+    fn from_bytes(bytes: &[u8]) -> Self;
+    fn to_bytes(&self) -> Vec<u8>;
+}
+
+macro_rules! primitive {
+    ($($rust:ty => $japl:ident),*) => {
+        $(
+            impl Primitive for $rust {
+                const TYPE: Type = Type::$japl;
+
+                fn from_bytes(bytes: &[u8]) -> Self {
+                    Self::from_ne_bytes(bytes.try_into().expect("size is checked by the caller"))
+                }
+
+                fn to_bytes(&self) -> Vec<u8> {
+                    self.to_ne_bytes().to_vec()
+                }
+            }
+        )*
+    };
+}
+
+primitive!(
+    i8 => Int8,
+    i16 => Int16,
+    i32 => Int32,
+    i64 => Int64,
+    u8 => Uint8,
+    u16 => Uint16,
+    u32 => Uint32,
+    u64 => Uint64,
+    f32 => Float32,
+    f64 => Float64
+);
+
+impl Primitive for bool {
+    const TYPE: Type = Type::Boolean;
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        bytes[0] != 0
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        vec![*self as u8]
+    }
+}
+
+impl Primitive for char {
+    const TYPE: Type = Type::Character;
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        char::from_u32(u32::from_bytes(bytes)).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        (*self as u32).to_bytes()
+    }
+}
@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use crate::alias::{Labels, Name};
+
+use super::{Instruction, Slot};
+
+#[derive(Debug, Default)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    pub labels: Labels,
+    // read-only data segment holding the string literals, mapped at address 0
+    pub data: Vec<u8>,
+    // the global variables by name, filled in by `parser::resolve`
+    pub globals: HashMap<Name, Slot>,
+    pub globals_size: usize,
+}
+
+impl Program {
+    // copies a nul-terminated string into the data segment and returns its address
+    pub fn intern(&mut self, bytes: &[u8]) -> usize {
+        let addr = self.data.len();
+
+        self.data.extend_from_slice(bytes);
+        self.data.push(0);
+
+        addr
+    }
+}
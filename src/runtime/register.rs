@@ -1,4 +1,6 @@
-use super::{BinOperator, UnOperator};
+use super::arithmetic::{self, integer_cmp, integer_flags, integer_op};
+use super::{BinOperator, Conversion, Operand, Type, UnOperator};
+use crate::error::Cause;
 use crate::lexer::Keyword;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -171,6 +173,60 @@ pub enum RegisterClass {
     I,
 }
 
+impl RegisterClass {
+    pub fn size(&self) -> usize {
+        match self {
+            RegisterClass::A | RegisterClass::I => 1,
+            RegisterClass::B => 2,
+            RegisterClass::C | RegisterClass::F => 4,
+            RegisterClass::D | RegisterClass::G => 8,
+        }
+    }
+
+    // the type the contents of this class are printed and read as
+    pub fn value_type(&self) -> Type {
+        match self {
+            RegisterClass::A => Type::Uint8,
+            RegisterClass::B => Type::Uint16,
+            RegisterClass::C => Type::Uint32,
+            RegisterClass::D => Type::Uint64,
+            RegisterClass::F => Type::Float32,
+            RegisterClass::G => Type::Float64,
+            RegisterClass::I => Type::Boolean,
+        }
+    }
+
+    // the type `sprint` and `sread` use for the integer classes
+    pub fn signed_type(&self) -> Option<Type> {
+        match self {
+            RegisterClass::A => Some(Type::Int8),
+            RegisterClass::B => Some(Type::Int16),
+            RegisterClass::C => Some(Type::Int32),
+            RegisterClass::D => Some(Type::Int64),
+            _ => None,
+        }
+    }
+
+    // every type a register of this class can be printed and read as
+    pub fn io_types(&self) -> Vec<Type> {
+        let mut types = vec![self.value_type()];
+        types.extend(self.signed_type());
+
+        if *self == RegisterClass::C {
+            types.push(Type::Character);
+        }
+
+        types
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            RegisterClass::A | RegisterClass::B | RegisterClass::C | RegisterClass::D
+        )
+    }
+}
+
 impl TryFrom<Keyword> for RegisterClass {
     type Error = ();
 
@@ -188,6 +244,48 @@ impl TryFrom<Keyword> for RegisterClass {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Flag {
+    Zero,
+    Carry,
+    Overflow,
+    Sign,
+}
+
+impl TryFrom<Keyword> for Flag {
+    type Error = ();
+
+    fn try_from(value: Keyword) -> Result<Self, ()> {
+        match value {
+            Keyword::JumpZero => Ok(Self::Zero),
+            Keyword::JumpCarry => Ok(Self::Carry),
+            Keyword::JumpOverflow => Ok(Self::Overflow),
+            Keyword::JumpSign => Ok(Self::Sign),
+            _ => Err(()),
+        }
+    }
+}
+
+// set by every operation from its result
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Flags {
+    pub zero: bool,
+    pub carry: bool,
+    pub overflow: bool,
+    pub sign: bool,
+}
+
+impl Flags {
+    pub fn get(&self, flag: Flag) -> bool {
+        match flag {
+            Flag::Zero => self.zero,
+            Flag::Carry => self.carry,
+            Flag::Overflow => self.overflow,
+            Flag::Sign => self.sign,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Register {
     pub a: [u8; 4],
@@ -197,426 +295,420 @@ pub struct Register {
     pub f: [f32; 4],
     pub g: [f64; 4],
     pub i: [bool; 4],
+    pub flags: Flags,
 }
 
 impl Register {
+    pub fn read_int(&self, reg: RegisterName) -> u64 {
+        match reg.register_class() {
+            RegisterClass::A => self.a[reg.index()] as u64,
+            RegisterClass::B => self.b[reg.index()] as u64,
+            RegisterClass::C => self.c[reg.index()] as u64,
+            RegisterClass::D => self.d[reg.index()],
+            _ => unreachable!("{:?} is not an integer register", reg),
+        }
+    }
+
+    pub fn to_bytes(&self, reg: RegisterName) -> Vec<u8> {
+        match reg.register_class() {
+            RegisterClass::A => self.a[reg.index()].to_ne_bytes().to_vec(),
+            RegisterClass::B => self.b[reg.index()].to_ne_bytes().to_vec(),
+            RegisterClass::C => self.c[reg.index()].to_ne_bytes().to_vec(),
+            RegisterClass::D => self.d[reg.index()].to_ne_bytes().to_vec(),
+            RegisterClass::F => self.f[reg.index()].to_ne_bytes().to_vec(),
+            RegisterClass::G => self.g[reg.index()].to_ne_bytes().to_vec(),
+            RegisterClass::I => vec![self.i[reg.index()] as u8],
+        }
+    }
+
+    // keeps the low bits of `value` that fit the register
+    pub fn write_int(&mut self, reg: RegisterName, value: u64) {
+        match reg.register_class() {
+            RegisterClass::A => self.a[reg.index()] = value as u8,
+            RegisterClass::B => self.b[reg.index()] = value as u16,
+            RegisterClass::C => self.c[reg.index()] = value as u32,
+            RegisterClass::D => self.d[reg.index()] = value,
+            _ => unreachable!("{:?} is not an integer register", reg),
+        }
+    }
+
+    pub fn read_float(&self, reg: RegisterName) -> f64 {
+        match reg.register_class() {
+            RegisterClass::F => self.f[reg.index()] as f64,
+            RegisterClass::G => self.g[reg.index()],
+            _ => unreachable!("{:?} is not a float register", reg),
+        }
+    }
+
+    // rounds to the nearest f32 for the f registers
+    pub fn write_float(&mut self, reg: RegisterName, value: f64) {
+        match reg.register_class() {
+            RegisterClass::F => self.f[reg.index()] = value as f32,
+            RegisterClass::G => self.g[reg.index()] = value,
+            _ => unreachable!("{:?} is not a float register", reg),
+        }
+    }
+
+    pub fn copy(&mut self, src: RegisterName, dst: RegisterName) -> Result<(), Cause> {
+        let class = src.register_class();
+
+        if dst.register_class() != class {
+            return Err(Cause::RegisterMismatch);
+        }
+
+        match class {
+            RegisterClass::F | RegisterClass::G => self.write_float(dst, self.read_float(src)),
+            RegisterClass::I => self.i[dst.index()] = self.i[src.index()],
+            _ => self.write_int(dst, self.read_int(src)),
+        }
+
+        Ok(())
+    }
+
+    pub fn convert(
+        &mut self,
+        conversion: Conversion,
+        src: RegisterName,
+        dst: RegisterName,
+    ) -> Result<(), Cause> {
+        let to = dst.register_class();
+
+        if !conversion.accepts(src, dst) {
+            return Err(Cause::RegisterMismatch);
+        }
+
+        let (src_bits, dst_bits) = (src.size() as i32 * 8, dst.size() as i32 * 8);
+
+        match conversion {
+            // `write_int` keeps the low bits, which is all truncation needs
+            Conversion::ZeroExtend | Conversion::Truncate => {
+                self.write_int(dst, self.read_int(src))
+            }
+            Conversion::SignExtend => {
+                let value = arithmetic::to_signed(self.read_int(src), src_bits as u32);
+                self.write_int(dst, value as u64);
+            }
+            Conversion::IntToFloat => match to {
+                RegisterClass::F => self.f[dst.index()] = self.read_int(src) as f32,
+                _ => self.g[dst.index()] = self.read_int(src) as f64,
+            },
+            Conversion::SignedIntToFloat => {
+                let value = arithmetic::to_signed(self.read_int(src), src_bits as u32) as i64;
+
+                match to {
+                    RegisterClass::F => self.f[dst.index()] = value as f32,
+                    _ => self.g[dst.index()] = value as f64,
+                }
+            }
+            Conversion::FloatToInt | Conversion::FloatToSignedInt => {
+                // rounds towards zero, values that do not fit and NaN are an error
+                let value = self.read_float(src).trunc();
+                let (min, max) = match conversion {
+                    Conversion::FloatToInt => (0.0, 2f64.powi(dst_bits)),
+                    _ => (-(2f64.powi(dst_bits - 1)), 2f64.powi(dst_bits - 1)),
+                };
+
+                if !(value >= min && value < max) {
+                    return Err(Cause::IntegerOverflow);
+                }
+
+                self.write_int(dst, value as i128 as u64);
+            }
+            Conversion::FloatToFloat => self.write_float(dst, self.read_float(src)),
+            Conversion::BoolToInt => self.write_int(dst, self.i[src.index()] as u64),
+            Conversion::IntToBool => self.i[dst.index()] = self.read_int(src) != 0,
+        }
+
+        Ok(())
+    }
+
     pub fn un_operate(
         &mut self,
         op: UnOperator,
         src: RegisterName,
         dst: RegisterName,
-    ) -> Result<(), ()> {
-        match (op, dst.register_class(), src.register_class()) {
-            (UnOperator::Not, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = !self.a[src.index()];
-            }
-            (UnOperator::Not, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = !self.b[src.index()];
-            }
-            (UnOperator::Not, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = !self.c[src.index()];
-            }
-            (UnOperator::Not, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = !self.d[src.index()];
-            }
+    ) -> Result<(), Cause> {
+        let class = src.register_class();
+
+        if class.is_integer() {
+            if dst.register_class() != class {
+                return Err(Cause::RegisterMismatch);
+            }
+
+            let bits = src.size() as u32 * 8;
+            let value = self.read_int(src);
+
+            // every unary operation is a binary one with a constant right hand side
+            let (op, rhs) = match op {
+                UnOperator::Not => (BinOperator::Xor, arithmetic::mask(bits)),
+                UnOperator::Increment => (BinOperator::Add, 1),
+                UnOperator::Decrement => (BinOperator::Subtract, 1),
+                UnOperator::SignedIncrement => (BinOperator::SignedAdd, 1),
+                UnOperator::SignedDecrement => (BinOperator::SignedSubtract, 1),
+                UnOperator::WrappingIncrement => (BinOperator::WrappingAdd, 1),
+                UnOperator::WrappingDecrement => (BinOperator::WrappingSubtract, 1),
+                UnOperator::SaturatingIncrement => (BinOperator::SaturatingAdd, 1),
+                UnOperator::SaturatingDecrement => (BinOperator::SaturatingSubtract, 1),
+            };
+
+            let result = integer_op(op, value, rhs, false, bits)?;
+
+            self.write_int(dst, result);
+            self.flags = integer_flags(op, value, rhs, false, result, bits);
+            return Ok(());
+        }
+
+        match (op, dst.register_class(), class) {
             (UnOperator::Not, RegisterClass::I, RegisterClass::I) => {
                 self.i[dst.index()] = !self.i[src.index()];
             }
-            (UnOperator::Increment, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src.index()] + 1;
-            }
-            (UnOperator::Increment, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = self.b[src.index()] + 1;
-            }
-            (UnOperator::Increment, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = self.c[src.index()] + 1;
-            }
-            (UnOperator::Increment, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = self.d[src.index()] + 1;
-            }
             (UnOperator::Increment, RegisterClass::F, RegisterClass::F) => {
                 self.f[dst.index()] = self.f[src.index()] + 1.0;
             }
             (UnOperator::Increment, RegisterClass::G, RegisterClass::G) => {
                 self.g[dst.index()] = self.g[src.index()] + 1.0;
             }
-            (UnOperator::Decrement, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src.index()] - 1;
-            }
-            (UnOperator::Decrement, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = self.b[src.index()] - 1;
-            }
-            (UnOperator::Decrement, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = self.c[src.index()] - 1;
-            }
-            (UnOperator::Decrement, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = self.d[src.index()] - 1;
-            }
             (UnOperator::Decrement, RegisterClass::F, RegisterClass::F) => {
                 self.f[dst.index()] = self.f[src.index()] - 1.0;
             }
             (UnOperator::Decrement, RegisterClass::G, RegisterClass::G) => {
                 self.g[dst.index()] = self.g[src.index()] - 1.0;
             }
-            _ => return Err(()),
+            _ => return Err(Cause::RegisterMismatch),
         }
 
+        self.flags = self.value_flags(dst);
         Ok(())
     }
 
     pub fn bin_operate(
         &mut self,
         op: BinOperator,
-        src1: RegisterName,
-        src2: RegisterName,
+        lhs: Operand,
+        rhs: Operand,
         dst: RegisterName,
-    ) -> Result<(), ()> {
-        match (
-            op,
-            src1.register_class(),
-            src2.register_class(),
-            dst.register_class(),
-        ) {
-            (BinOperator::Add, RegisterClass::A, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src1.index()] + self.a[src2.index()]
-            }
-            (BinOperator::Add, RegisterClass::B, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = self.b[src1.index()] + self.b[src2.index()]
-            }
-            (BinOperator::Add, RegisterClass::C, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = self.c[src1.index()] + self.c[src2.index()]
-            }
-            (BinOperator::Add, RegisterClass::D, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = self.d[src1.index()] + self.d[src2.index()]
-            }
-            (BinOperator::Add, RegisterClass::F, RegisterClass::F, RegisterClass::F) => {
-                self.f[dst.index()] = self.f[src1.index()] + self.f[src2.index()]
-            }
-            (BinOperator::Add, RegisterClass::G, RegisterClass::G, RegisterClass::G) => {
-                self.g[dst.index()] = self.g[src1.index()] + self.g[src2.index()]
-            }
+    ) -> Result<(), Cause> {
+        // immediates take the class of the register they are paired with
+        let class = match (lhs, rhs) {
+            (Operand::Register(i), _) | (_, Operand::Register(i)) => i.register_class(),
+            _ => return Err(Cause::RegisterMismatch),
+        };
 
-            (BinOperator::Subtract, RegisterClass::A, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src1.index()] - self.a[src2.index()]
-            }
-            (BinOperator::Subtract, RegisterClass::B, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = self.b[src1.index()] - self.b[src2.index()]
-            }
-            (BinOperator::Subtract, RegisterClass::C, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = self.c[src1.index()] - self.c[src2.index()]
-            }
-            (BinOperator::Subtract, RegisterClass::D, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = self.d[src1.index()] - self.d[src2.index()]
-            }
-            (BinOperator::Subtract, RegisterClass::F, RegisterClass::F, RegisterClass::F) => {
-                self.f[dst.index()] = self.f[src1.index()] - self.f[src2.index()]
-            }
-            (BinOperator::Subtract, RegisterClass::G, RegisterClass::G, RegisterClass::G) => {
-                self.g[dst.index()] = self.g[src1.index()] - self.g[src2.index()]
-            }
+        let expected = if op.is_comparison() {
+            RegisterClass::I
+        } else {
+            class
+        };
 
-            (BinOperator::Multiply, RegisterClass::A, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src1.index()] * self.a[src2.index()]
-            }
-            (BinOperator::Multiply, RegisterClass::B, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = self.b[src1.index()] * self.b[src2.index()]
-            }
-            (BinOperator::Multiply, RegisterClass::C, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = self.c[src1.index()] * self.c[src2.index()]
-            }
-            (BinOperator::Multiply, RegisterClass::D, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = self.d[src1.index()] * self.d[src2.index()]
-            }
-            (BinOperator::Multiply, RegisterClass::F, RegisterClass::F, RegisterClass::F) => {
-                self.f[dst.index()] = self.f[src1.index()] * self.f[src2.index()]
-            }
-            (BinOperator::Multiply, RegisterClass::G, RegisterClass::G, RegisterClass::G) => {
-                self.g[dst.index()] = self.g[src1.index()] * self.g[src2.index()]
-            }
+        let mismatched = [lhs, rhs]
+            .iter()
+            .any(|i| matches!(i, Operand::Register(reg) if reg.register_class() != class));
 
-            (BinOperator::Divide, RegisterClass::A, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src1.index()] / self.a[src2.index()]
-            }
-            (BinOperator::Divide, RegisterClass::B, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = self.b[src1.index()] / self.b[src2.index()]
-            }
-            (BinOperator::Divide, RegisterClass::C, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = self.c[src1.index()] / self.c[src2.index()]
-            }
-            (BinOperator::Divide, RegisterClass::D, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = self.d[src1.index()] / self.d[src2.index()]
-            }
-            (BinOperator::Divide, RegisterClass::F, RegisterClass::F, RegisterClass::F) => {
-                self.f[dst.index()] = self.f[src1.index()] / self.f[src2.index()]
-            }
-            (BinOperator::Divide, RegisterClass::G, RegisterClass::G, RegisterClass::G) => {
-                self.g[dst.index()] = self.g[src1.index()] / self.g[src2.index()]
-            }
+        if mismatched || dst.register_class() != expected {
+            return Err(Cause::RegisterMismatch);
+        }
 
-            (BinOperator::Modulus, RegisterClass::A, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src1.index()] % self.a[src2.index()]
-            }
-            (BinOperator::Modulus, RegisterClass::B, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = self.b[src1.index()] % self.b[src2.index()]
-            }
-            (BinOperator::Modulus, RegisterClass::C, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = self.c[src1.index()] % self.c[src2.index()]
-            }
-            (BinOperator::Modulus, RegisterClass::D, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = self.d[src1.index()] % self.d[src2.index()]
-            }
+        let (lhs, rhs) = (self.read_operand(lhs), self.read_operand(rhs));
 
-            (BinOperator::LeftShift, RegisterClass::A, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src1.index()] << self.a[src2.index()]
-            }
-            (BinOperator::LeftShift, RegisterClass::B, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = self.b[src1.index()] << self.b[src2.index()]
-            }
-            (BinOperator::LeftShift, RegisterClass::C, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = self.c[src1.index()] << self.c[src2.index()]
-            }
-            (BinOperator::LeftShift, RegisterClass::D, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = self.d[src1.index()] << self.d[src2.index()]
-            }
+        match class {
+            RegisterClass::A | RegisterClass::B | RegisterClass::C | RegisterClass::D => {
+                let bits = class.size() as u32 * 8;
 
-            (BinOperator::RightShift, RegisterClass::A, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src1.index()] >> self.a[src2.index()]
-            }
-            (BinOperator::RightShift, RegisterClass::B, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = self.b[src1.index()] >> self.b[src2.index()]
-            }
-            (BinOperator::RightShift, RegisterClass::C, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = self.c[src1.index()] >> self.c[src2.index()]
-            }
-            (BinOperator::RightShift, RegisterClass::D, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = self.d[src1.index()] >> self.d[src2.index()]
-            }
+                if op.is_comparison() {
+                    self.i[dst.index()] = integer_cmp(op, lhs, rhs, bits);
+                    self.flags = self.value_flags(dst);
+                } else {
+                    let carry = self.flags.carry;
+                    let result = integer_op(op, lhs, rhs, carry, bits)?;
 
-            (BinOperator::And, RegisterClass::A, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src1.index()] & self.a[src2.index()]
-            }
-            (BinOperator::And, RegisterClass::B, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = self.b[src1.index()] & self.b[src2.index()]
-            }
-            (BinOperator::And, RegisterClass::C, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = self.c[src1.index()] & self.c[src2.index()]
-            }
-            (BinOperator::And, RegisterClass::D, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = self.d[src1.index()] & self.d[src2.index()]
-            }
-            (BinOperator::And, RegisterClass::I, RegisterClass::I, RegisterClass::I) => {
-                self.i[dst.index()] = self.i[src1.index()] && self.i[src2.index()]
-            }
+                    self.write_int(dst, result);
+                    self.flags = integer_flags(op, lhs, rhs, carry, result, bits);
+                }
 
-            (BinOperator::Or, RegisterClass::A, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src1.index()] | self.a[src2.index()]
-            }
-            (BinOperator::Or, RegisterClass::B, RegisterClass::B, RegisterClass::B) => {
-                self.b[dst.index()] = self.b[src1.index()] | self.b[src2.index()]
-            }
-            (BinOperator::Or, RegisterClass::C, RegisterClass::C, RegisterClass::C) => {
-                self.c[dst.index()] = self.c[src1.index()] | self.c[src2.index()]
-            }
-            (BinOperator::Or, RegisterClass::D, RegisterClass::D, RegisterClass::D) => {
-                self.d[dst.index()] = self.d[src1.index()] | self.d[src2.index()]
-            }
-            (BinOperator::Or, RegisterClass::I, RegisterClass::I, RegisterClass::I) => {
-                self.i[dst.index()] = self.i[src1.index()] || self.i[src2.index()]
+                return Ok(());
             }
 
-            (BinOperator::Xor, RegisterClass::A, RegisterClass::A, RegisterClass::A) => {
-                self.a[dst.index()] = self.a[src1.index()] ^ self.a[src2.index()]
-            }
-            (BinOperator::Xor, RegisterClass::B, RegisterClass::B, RegisterClass::B) => {
-                self.a[dst.index()] = self.a[src1.index()] ^ self.a[src2.index()]
-            }
-            (BinOperator::Xor, RegisterClass::C, RegisterClass::C, RegisterClass::C) => {
-                self.a[dst.index()] = self.a[src1.index()] ^ self.a[src2.index()]
-            }
-            (BinOperator::Xor, RegisterClass::D, RegisterClass::D, RegisterClass::D) => {
-                self.a[dst.index()] = self.a[src1.index()] ^ self.a[src2.index()]
-            }
-            (BinOperator::Xor, RegisterClass::I, RegisterClass::I, RegisterClass::I) => {
-                self.a[dst.index()] = self.a[src1.index()] ^ self.a[src2.index()]
-            }
+            // f32 operations are done in f64 and rounded back, which gives the same result
+            RegisterClass::F | RegisterClass::G => {
+                let (lhs, rhs) = (to_float(class, lhs), to_float(class, rhs));
 
-            (BinOperator::Equals, RegisterClass::A, RegisterClass::A, RegisterClass::I) => {
-                self.i[dst.index()] = self.a[src1.index()] == self.a[src2.index()]
-            }
-            (BinOperator::Equals, RegisterClass::B, RegisterClass::B, RegisterClass::I) => {
-                self.i[dst.index()] = self.b[src1.index()] == self.b[src2.index()]
-            }
-            (BinOperator::Equals, RegisterClass::C, RegisterClass::C, RegisterClass::I) => {
-                self.i[dst.index()] = self.c[src1.index()] == self.c[src2.index()]
-            }
-            (BinOperator::Equals, RegisterClass::D, RegisterClass::D, RegisterClass::I) => {
-                self.i[dst.index()] = self.d[src1.index()] == self.d[src2.index()]
-            }
-            (BinOperator::Equals, RegisterClass::F, RegisterClass::F, RegisterClass::I) => {
-                self.i[dst.index()] = self.f[src1.index()] == self.f[src2.index()]
-            }
-            (BinOperator::Equals, RegisterClass::G, RegisterClass::G, RegisterClass::I) => {
-                self.i[dst.index()] = self.g[src1.index()] == self.g[src2.index()]
-            }
-            (BinOperator::Equals, RegisterClass::I, RegisterClass::I, RegisterClass::I) => {
-                self.i[dst.index()] = self.i[src1.index()] == self.i[src2.index()]
-            }
+                match op {
+                    BinOperator::Add => self.write_float(dst, lhs + rhs),
+                    BinOperator::Subtract => self.write_float(dst, lhs - rhs),
+                    BinOperator::Multiply => self.write_float(dst, lhs * rhs),
+                    BinOperator::Divide => self.write_float(dst, lhs / rhs),
 
-            (BinOperator::NotEquals, RegisterClass::A, RegisterClass::A, RegisterClass::I) => {
-                self.i[dst.index()] = self.a[src1.index()] != self.a[src2.index()]
-            }
-            (BinOperator::NotEquals, RegisterClass::B, RegisterClass::B, RegisterClass::I) => {
-                self.i[dst.index()] = self.b[src1.index()] != self.b[src2.index()]
-            }
-            (BinOperator::NotEquals, RegisterClass::C, RegisterClass::C, RegisterClass::I) => {
-                self.i[dst.index()] = self.c[src1.index()] != self.c[src2.index()]
-            }
-            (BinOperator::NotEquals, RegisterClass::D, RegisterClass::D, RegisterClass::I) => {
-                self.i[dst.index()] = self.d[src1.index()] != self.d[src2.index()]
-            }
-            (BinOperator::NotEquals, RegisterClass::F, RegisterClass::F, RegisterClass::I) => {
-                self.i[dst.index()] = self.f[src1.index()] != self.f[src2.index()]
-            }
-            (BinOperator::NotEquals, RegisterClass::G, RegisterClass::G, RegisterClass::I) => {
-                self.i[dst.index()] = self.g[src1.index()] != self.g[src2.index()]
-            }
-            (BinOperator::NotEquals, RegisterClass::I, RegisterClass::I, RegisterClass::I) => {
-                self.i[dst.index()] = self.i[src1.index()] != self.i[src2.index()]
-            }
+                    BinOperator::Equals => self.i[dst.index()] = lhs == rhs,
+                    BinOperator::NotEquals => self.i[dst.index()] = lhs != rhs,
+                    BinOperator::LessThan => self.i[dst.index()] = lhs < rhs,
+                    BinOperator::GreaterThan => self.i[dst.index()] = lhs > rhs,
+                    BinOperator::LessThanEqualTo => self.i[dst.index()] = lhs <= rhs,
+                    BinOperator::GreaterThanEqualTo => self.i[dst.index()] = lhs >= rhs,
 
-            (BinOperator::LessThan, RegisterClass::A, RegisterClass::A, RegisterClass::I) => {
-                self.i[dst.index()] = self.a[src1.index()] < self.a[src2.index()]
-            }
-            (BinOperator::LessThan, RegisterClass::B, RegisterClass::B, RegisterClass::I) => {
-                self.i[dst.index()] = self.b[src1.index()] < self.b[src2.index()]
-            }
-            (BinOperator::LessThan, RegisterClass::C, RegisterClass::C, RegisterClass::I) => {
-                self.i[dst.index()] = self.c[src1.index()] < self.c[src2.index()]
-            }
-            (BinOperator::LessThan, RegisterClass::D, RegisterClass::D, RegisterClass::I) => {
-                self.i[dst.index()] = self.d[src1.index()] < self.d[src2.index()]
-            }
-            (BinOperator::LessThan, RegisterClass::F, RegisterClass::F, RegisterClass::I) => {
-                self.i[dst.index()] = self.f[src1.index()] < self.f[src2.index()]
-            }
-            (BinOperator::LessThan, RegisterClass::G, RegisterClass::G, RegisterClass::I) => {
-                self.i[dst.index()] = self.g[src1.index()] < self.g[src2.index()]
-            }
-            (BinOperator::LessThan, RegisterClass::I, RegisterClass::I, RegisterClass::I) => {
-                self.i[dst.index()] = self.i[src1.index()] < self.i[src2.index()]
+                    _ => return Err(Cause::RegisterMismatch),
+                }
             }
 
-            (BinOperator::GreaterThan, RegisterClass::A, RegisterClass::A, RegisterClass::I) => {
-                self.i[dst.index()] = self.a[src1.index()] > self.a[src2.index()]
-            }
-            (BinOperator::GreaterThan, RegisterClass::B, RegisterClass::B, RegisterClass::I) => {
-                self.i[dst.index()] = self.b[src1.index()] > self.b[src2.index()]
-            }
-            (BinOperator::GreaterThan, RegisterClass::C, RegisterClass::C, RegisterClass::I) => {
-                self.i[dst.index()] = self.c[src1.index()] > self.c[src2.index()]
-            }
-            (BinOperator::GreaterThan, RegisterClass::D, RegisterClass::D, RegisterClass::I) => {
-                self.i[dst.index()] = self.d[src1.index()] > self.d[src2.index()]
-            }
-            (BinOperator::GreaterThan, RegisterClass::F, RegisterClass::F, RegisterClass::I) => {
-                self.i[dst.index()] = self.f[src1.index()] > self.f[src2.index()]
-            }
-            (BinOperator::GreaterThan, RegisterClass::G, RegisterClass::G, RegisterClass::I) => {
-                self.i[dst.index()] = self.g[src1.index()] > self.g[src2.index()]
-            }
-            (BinOperator::GreaterThan, RegisterClass::I, RegisterClass::I, RegisterClass::I) => {
-                self.i[dst.index()] = self.i[src1.index()] > self.i[src2.index()]
+            RegisterClass::I => {
+                let (lhs, rhs) = (lhs != 0, rhs != 0);
+
+                self.i[dst.index()] = match op {
+                    BinOperator::And => lhs && rhs,
+                    BinOperator::Or => lhs || rhs,
+                    BinOperator::Xor => lhs ^ rhs,
+
+                    BinOperator::Equals => lhs == rhs,
+                    BinOperator::NotEquals => lhs != rhs,
+                    BinOperator::LessThan => !lhs & rhs,
+                    BinOperator::GreaterThan => lhs & !rhs,
+                    BinOperator::LessThanEqualTo => lhs <= rhs,
+                    BinOperator::GreaterThanEqualTo => lhs >= rhs,
+
+                    _ => return Err(Cause::RegisterMismatch),
+                };
             }
+        }
 
-            (
-                BinOperator::LessThanEqualTo,
-                RegisterClass::A,
-                RegisterClass::A,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.a[src1.index()] <= self.a[src2.index()],
-            (
-                BinOperator::LessThanEqualTo,
-                RegisterClass::B,
-                RegisterClass::B,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.b[src1.index()] <= self.b[src2.index()],
-            (
-                BinOperator::LessThanEqualTo,
-                RegisterClass::C,
-                RegisterClass::C,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.c[src1.index()] <= self.c[src2.index()],
-            (
-                BinOperator::LessThanEqualTo,
-                RegisterClass::D,
-                RegisterClass::D,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.d[src1.index()] <= self.d[src2.index()],
-            (
-                BinOperator::LessThanEqualTo,
-                RegisterClass::F,
-                RegisterClass::F,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.f[src1.index()] <= self.f[src2.index()],
-            (
-                BinOperator::LessThanEqualTo,
-                RegisterClass::G,
-                RegisterClass::G,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.g[src1.index()] <= self.g[src2.index()],
-            (
-                BinOperator::LessThanEqualTo,
-                RegisterClass::I,
-                RegisterClass::I,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.i[src1.index()] <= self.i[src2.index()],
-
-            (
-                BinOperator::GreaterThanEqualTo,
-                RegisterClass::A,
-                RegisterClass::A,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.a[src1.index()] >= self.a[src2.index()],
-            (
-                BinOperator::GreaterThanEqualTo,
-                RegisterClass::B,
-                RegisterClass::B,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.b[src1.index()] >= self.b[src2.index()],
-            (
-                BinOperator::GreaterThanEqualTo,
-                RegisterClass::C,
-                RegisterClass::C,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.c[src1.index()] >= self.c[src2.index()],
-            (
-                BinOperator::GreaterThanEqualTo,
-                RegisterClass::D,
-                RegisterClass::D,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.d[src1.index()] >= self.d[src2.index()],
-            (
-                BinOperator::GreaterThanEqualTo,
-                RegisterClass::F,
-                RegisterClass::F,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.f[src1.index()] >= self.f[src2.index()],
-            (
-                BinOperator::GreaterThanEqualTo,
-                RegisterClass::G,
-                RegisterClass::G,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.g[src1.index()] >= self.g[src2.index()],
-            (
-                BinOperator::GreaterThanEqualTo,
-                RegisterClass::I,
-                RegisterClass::I,
-                RegisterClass::I,
-            ) => self.i[dst.index()] = self.i[src1.index()] >= self.i[src2.index()],
-
-            _ => return Err(()),
+        self.flags = self.value_flags(dst);
+        Ok(())
+    }
+
+    // the raw bits of a register, laid out like an immediate of its class
+    fn read_operand(&self, operand: Operand) -> u64 {
+        let reg = match operand {
+            Operand::Register(i) => i,
+            Operand::Immediate(i) => return i,
         };
 
-        Ok(())
+        match reg.register_class() {
+            RegisterClass::F => self.f[reg.index()].to_bits() as u64,
+            RegisterClass::G => self.g[reg.index()].to_bits(),
+            RegisterClass::I => self.i[reg.index()] as u64,
+            _ => self.read_int(reg),
+        }
+    }
+
+    // float and boolean results only ever set zero and sign
+    fn value_flags(&self, reg: RegisterName) -> Flags {
+        let (zero, sign) = match reg.register_class() {
+            RegisterClass::F => (self.f[reg.index()] == 0.0, self.f[reg.index()] < 0.0),
+            RegisterClass::G => (self.g[reg.index()] == 0.0, self.g[reg.index()] < 0.0),
+            RegisterClass::I => (!self.i[reg.index()], false),
+            _ => unreachable!("{:?} is an integer register", reg),
+        };
+
+        Flags {
+            zero,
+            sign,
+            ..Flags::default()
+        }
+    }
+}
+
+fn to_float(class: RegisterClass, bits: u64) -> f64 {
+    match class {
+        RegisterClass::F => f32::from_bits(bits as u32) as f64,
+        _ => f64::from_bits(bits),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Cause;
+    use crate::Vm;
+
+    fn run(source: &str) -> Vm {
+        let mut vm = Vm::new(crate::compile(source).unwrap()).unwrap();
+        vm.run().unwrap();
+        vm
+    }
+
+    #[test]
+    fn adds_128_bit_numbers_with_addw_and_adc() {
+        // (2^64 + 2^64 - 1) + (2 * 2^64 + 1) = 4 * 2^64
+        let vm = run("push uint64 low
+push uint64 high
+load 18446744073709551615 d0
+load 1 d1
+load 1 d2
+load 2 d3
+addw d0 d2 d0
+adc d1 d3 d1
+unload d0 low
+unload d1 high");
+
+        assert_eq!(vm.get::<u64>("low"), Ok(0));
+        assert_eq!(vm.get::<u64>("high"), Ok(4));
+    }
+
+    #[test]
+    fn subtracts_128_bit_numbers_with_subw_and_sbb() {
+        // (1 * 2^64 + 0) - 1 = 0 * 2^64 + (2^64 - 1)
+        let vm = run("push uint64 low
+push uint64 high
+load 0 d0
+load 1 d1
+subw d0 1 d0
+sbb d1 0 d1
+unload d0 low
+unload d1 high");
+
+        assert_eq!(vm.get::<u64>("low"), Ok(u64::MAX));
+        assert_eq!(vm.get::<u64>("high"), Ok(0));
+    }
+
+    #[test]
+    fn plain_add_traps_instead_of_carrying() {
+        let error = Vm::new(crate::compile("load 255 a0\nadd a0 1 a0").unwrap())
+            .unwrap()
+            .run()
+            .unwrap_err();
+
+        assert_eq!(error.cause, Cause::IntegerOverflow);
+        assert_eq!(error.pc, 1);
+    }
+
+    #[test]
+    fn branches_on_each_flag() {
+        // every branch has to be taken, and the ones on clear flags not, to reach `exit 4`
+        let status = crate::run(
+            "load 0 a1
+load 1 a0
+addw a0 1 a0
+jz fail
+jc fail
+jo fail
+js fail
+load 5 a0
+subw a0 5 a0
+jz zero
+exit a1
+zero:
+load 1 a1
+load 200 a0
+addw a0 100 a0
+jo fail
+jc carry
+exit a1
+carry:
+load 2 a1
+load 127 a0
+addw a0 1 a0
+jo overflow
+exit a1
+overflow:
+load 3 a1
+js sign
+exit a1
+sign:
+load 4 a1
+exit a1
+fail:
+load 99 a1
+exit a1",
+        )
+        .unwrap();
+
+        assert_eq!(status, 4);
     }
 }
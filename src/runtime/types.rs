@@ -1,4 +1,10 @@
-use crate::lexer::Keyword;
+use std::rc::Rc;
+
+use crate::alias::Str;
+use crate::lexer::{integer_range, Keyword, Literal};
+
+// the largest type the parser accepts, which keeps every frame offset far from overflowing
+pub const MAX_TYPE_SIZE: usize = u32::MAX as usize;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Type {
@@ -14,6 +20,56 @@ pub enum Type {
     Float64,
     Boolean,
     Character,
+    Struct(Rc<Struct>),
+    // the element type and the number of elements
+    Array(Box<Type>, usize),
+}
+
+// a record type declared with `struct`, its fields are laid out in order without padding
+#[derive(PartialEq, Eq)]
+pub struct Struct {
+    pub name: Str,
+    pub fields: Vec<Field>,
+    pub size: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: Str,
+    pub offset: usize,
+    pub field_type: Type,
+}
+
+impl Struct {
+    pub fn new(name: Str, fields: Vec<(Str, Type)>) -> Self {
+        let mut size = 0;
+        let fields = fields
+            .into_iter()
+            .map(|(name, field_type)| {
+                let offset = size;
+                size = field_type.size().saturating_add(size);
+
+                Field {
+                    name,
+                    offset,
+                    field_type,
+                }
+            })
+            .collect();
+
+        Self { name, fields, size }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|i| &*i.name == name)
+    }
+}
+
+// the fields are left out, they would make every error message that mentions a slot unreadable
+impl std::fmt::Debug for Struct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
 }
 
 impl Type {
@@ -31,8 +87,99 @@ impl Type {
             Type::Float64 => 8,
             Type::Boolean => 1,
             Type::Character => 4,
+            Type::Struct(i) => i.size,
+            Type::Array(i, len) => i.size().saturating_mul(*len),
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64)
+    }
+
+    // `bytes` must be exactly `size()` long
+    pub fn format(&self, bytes: &[u8]) -> String {
+        match self {
+            Type::Int8 => (bytes[0] as i8).to_string(),
+            Type::Int16 => i16::from_ne_bytes(array(bytes)).to_string(),
+            Type::Int32 => i32::from_ne_bytes(array(bytes)).to_string(),
+            Type::Int64 => i64::from_ne_bytes(array(bytes)).to_string(),
+            Type::Uint8 => bytes[0].to_string(),
+            Type::Uint16 => u16::from_ne_bytes(array(bytes)).to_string(),
+            Type::Uint32 => u32::from_ne_bytes(array(bytes)).to_string(),
+            Type::Uint64 => u64::from_ne_bytes(array(bytes)).to_string(),
+            Type::Float32 => f32::from_ne_bytes(array(bytes)).to_string(),
+            Type::Float64 => f64::from_ne_bytes(array(bytes)).to_string(),
+            Type::Boolean => (bytes[0] != 0).to_string(),
+            Type::Character => char::from_u32(u32::from_ne_bytes(array(bytes)))
+                .unwrap_or(char::REPLACEMENT_CHARACTER)
+                .to_string(),
+            Type::Struct(i) => {
+                let fields = i
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let end = field.offset + field.field_type.size();
+                        let value = field.field_type.format(&bytes[field.offset..end]);
+
+                        format!("{}: {}", field.name, value)
+                    })
+                    .collect::<Vec<_>>();
+
+                format!("{} {{ {} }}", i.name, fields.join(", "))
+            }
+            Type::Array(i, _) => {
+                let elements = bytes
+                    .chunks(i.size().max(1))
+                    .map(|element| i.format(element))
+                    .collect::<Vec<_>>();
+
+                format!("[{}]", elements.join(", "))
+            }
         }
     }
+
+    // parses a line of user input as a value of this type
+    pub fn parse(&self, input: &str) -> Result<Literal, Str> {
+        let input = input.trim_end_matches(['\n', '\r']);
+
+        match self {
+            Type::Float32 | Type::Float64 => input
+                .trim()
+                .parse()
+                .map(Literal::Float)
+                .map_err(|_| format!("`{}` is not a valid float", input).into()),
+            Type::Boolean => match input.trim() {
+                "true" => Ok(Literal::Boolean(true)),
+                "false" => Ok(Literal::Boolean(false)),
+                _ => Err(format!("`{}` is not a valid boolean", input).into()),
+            },
+            Type::Character => {
+                let mut chars = input.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(i), None) => Ok(Literal::Character(i)),
+                    _ => Err(format!("`{}` is not a single character", input).into()),
+                }
+            }
+            Type::Struct(i) => Err(format!("{} is a struct and cannot be read", i.name).into()),
+            Type::Array(..) => Err(format!("{:?} is an array and cannot be read", self).into()),
+            _ => {
+                let Ok(Literal::Integer(i, _)) = Literal::parse_number(input.trim()) else {
+                    return Err(format!("`{}` is not a valid integer", input).into());
+                };
+
+                let (min, max) = integer_range(self.size(), self.is_signed());
+                if i < min || i > max {
+                    return Err(format!("`{}` is out of range for {:?}", input, self).into());
+                }
+
+                Ok(Literal::Integer(i, None))
+            }
+        }
+    }
+}
+
+fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    bytes.try_into().expect("size is checked by the caller")
 }
 
 impl TryFrom<Keyword> for Type {
@@ -52,7 +199,7 @@ impl TryFrom<Keyword> for Type {
             Keyword::Float64 => Ok(Self::Float64),
             Keyword::Character => Ok(Self::Character),
             Keyword::Boolean => Ok(Self::Boolean),
-            _ => todo!(),
+            _ => Err(()),
         }
     }
 }
@@ -1,18 +1,19 @@
-use crate::alias::Name;
-use crate::lexer::{Literal, Token};
+use crate::lexer::{Literal, Span, Token};
 
-#[derive(Debug, PartialEq)]
+use super::Variable;
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Value {
     RValue(Literal),
-    LValue(Name),
+    LValue(Variable),
 }
 
-impl TryFrom<Token> for Value {
+impl TryFrom<(Token, Span)> for Value {
     type Error = ();
 
-    fn try_from(value: Token) -> Result<Self, Self::Error> {
+    fn try_from((value, span): (Token, Span)) -> Result<Self, Self::Error> {
         match value {
-            Token::Identifier(i) => Ok(Self::LValue(Name::from(i))),
+            Token::Identifier(i) => Ok(Self::LValue(Variable::Name(i.into(), Vec::new(), span))),
             Token::Literal(i) => Ok(Self::RValue(i)),
             _ => Err(()),
         }
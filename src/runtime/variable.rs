@@ -0,0 +1,36 @@
+use crate::alias::{Name, Str};
+use crate::lexer::Span;
+
+use super::{Operand, Type};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Scope {
+    Global,
+    // the frame of the function currently executing
+    Local,
+}
+
+// where a variable lives, as an offset from the base of its frame
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Slot {
+    pub scope: Scope,
+    pub offset: usize,
+    pub var_type: Type,
+}
+
+// the parser only knows the name and the fields accessed on it,
+// `parser::resolve` replaces them with the slot
+#[derive(Debug, PartialEq, Clone)]
+pub enum Variable {
+    Name(Name, Vec<Str>, Span),
+    Slot(Slot),
+}
+
+// `name[index]`, an element of an array variable picked at runtime
+#[derive(Debug, PartialEq, Clone)]
+pub struct Element {
+    // the array as written in the source, for error messages
+    pub name: Name,
+    pub array: Variable,
+    pub index: Operand,
+}
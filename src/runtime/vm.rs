@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use crate::alias::Name;
+use crate::error::{Cause, LinkError, MissingFunction, RuntimeError};
+
+use super::{
+    execute, Frame, Host, Instruction, Leak, Memory, Primitive, Program, Register, Slot, Type,
+    DEFAULT_MEMORY_LIMIT,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum State {
+    Running,
+    // the program stopped with this exit status
+    Halted(i32),
+}
+
+// a program together with everything it needs to execute, which can be stepped and inspected
+#[derive(Debug)]
+pub struct Vm {
+    instructions: Vec<Instruction>,
+    globals: HashMap<Name, Slot>,
+    frames: Vec<Frame>,
+    register: Register,
+    memory: Memory,
+    host: Host,
+    pc: usize,
+    state: State,
+}
+
+impl Vm {
+    pub fn new(program: Program) -> Result<Self, LinkError> {
+        Self::with_host(program, Host::new())
+    }
+
+    // links every `extern` in the program against the functions of `host`
+    pub fn with_host(program: Program, host: Host) -> Result<Self, LinkError> {
+        let Program {
+            instructions,
+            labels: _,
+            data,
+            globals,
+            globals_size,
+        } = program;
+
+        let missing: Vec<_> = instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(pc, instruction)| match instruction {
+                Instruction::Extern(name) if !host.contains(name) => Some(MissingFunction {
+                    pc,
+                    name: name.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(LinkError { missing });
+        }
+
+        let state = if instructions.is_empty() {
+            State::Halted(0)
+        } else {
+            State::Running
+        };
+
+        let mut vm = Self {
+            instructions,
+            globals,
+            // the first frame holds the globals and is never popped
+            frames: vec![Frame::new(0, data.len(), globals_size)],
+            register: Register::default(),
+            memory: Memory::new(DEFAULT_MEMORY_LIMIT, data),
+            host,
+            pc: 0,
+            state,
+        };
+
+        vm.reserve_globals();
+        Ok(vm)
+    }
+
+    pub fn set_memory_limit(&mut self, limit: usize) {
+        self.memory.set_limit(limit);
+        self.reserve_globals();
+    }
+
+    // executes a single instruction, stepping a halted program does nothing
+    pub fn step(&mut self) -> Result<State, RuntimeError> {
+        if self.state != State::Running {
+            return Ok(self.state);
+        }
+
+        let pc = self.pc;
+
+        let halted = execute(
+            &self.instructions,
+            &mut self.frames,
+            &mut self.register,
+            &mut self.memory,
+            &mut self.host,
+            &mut self.pc,
+        )
+        .map_err(|cause| RuntimeError {
+            pc,
+            instruction: Box::new(self.instructions[pc].clone()),
+            cause,
+        })?;
+
+        if let Some(i) = halted {
+            self.state = State::Halted(i);
+        } else if self.pc >= self.instructions.len() {
+            self.state = State::Halted(0);
+        }
+
+        Ok(self.state)
+    }
+
+    // runs until the program halts and returns its exit status
+    pub fn run(&mut self) -> Result<i32, RuntimeError> {
+        loop {
+            if let State::Halted(i) = self.step()? {
+                return Ok(i);
+            }
+        }
+    }
+
+    // runs at most `steps` instructions
+    pub fn run_for(&mut self, steps: usize) -> Result<State, RuntimeError> {
+        for _ in 0..steps {
+            if self.step()? != State::Running {
+                break;
+            }
+        }
+
+        Ok(self.state)
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    // index of the next instruction to execute
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn register(&self) -> &Register {
+        &self.register
+    }
+
+    pub fn register_mut(&mut self) -> &mut Register {
+        &mut self.register
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        self.memory.bytes()
+    }
+
+    // heap blocks that are still allocated
+    pub fn leaks(&self) -> Vec<Leak> {
+        self.memory.leaks()
+    }
+
+    // the type and bytes of a global variable, locals have no names left at runtime
+    pub fn variable(&self, name: &str) -> Result<(Type, &[u8]), Cause> {
+        let (start, var_type) = self.find_global(name)?;
+        let bytes = self.memory.read(start, start + var_type.size())?;
+
+        Ok((var_type, bytes))
+    }
+
+    pub fn get<T: Primitive>(&self, name: &str) -> Result<T, Cause> {
+        let (var_type, bytes) = self.variable(name)?;
+        expect_type::<T>(var_type)?;
+
+        Ok(T::from_bytes(bytes))
+    }
+
+    pub fn set<T: Primitive>(&mut self, name: &str, value: T) -> Result<(), Cause> {
+        let (start, var_type) = self.find_global(name)?;
+        let end = start + var_type.size();
+        expect_type::<T>(var_type)?;
+
+        self.memory
+            .write(start, end)?
+            .copy_from_slice(&value.to_bytes());
+
+        Ok(())
+    }
+
+    // the globals exist before the program runs so that they can be set, if they do not
+    // fit in the limit their `push` reports it instead
+    fn reserve_globals(&mut self) {
+        let _ = self.memory.reserve(self.frames[0].top());
+    }
+
+    fn find_global(&self, name: &str) -> Result<(usize, Type), Cause> {
+        let slot = self
+            .globals
+            .get(name)
+            .ok_or_else(|| Cause::UnknownVariable(name.into()))?;
+
+        Ok((self.frames[0].base + slot.offset, slot.var_type.clone()))
+    }
+}
+
+fn expect_type<T: Primitive>(found: Type) -> Result<(), Cause> {
+    if found != T::TYPE {
+        return Err(Cause::TypeMismatch {
+            expected: T::TYPE,
+            found,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_every_missing_host_function() {
+        let program = crate::compile("extern first\nextern known\nextern second").unwrap();
+
+        let mut host = Host::new();
+        host.register("known", |_, _| Ok(()));
+
+        let error = Vm::with_host(program, host).unwrap_err();
+
+        assert_eq!(
+            error.missing,
+            [
+                MissingFunction {
+                    pc: 0,
+                    name: "first".into()
+                },
+                MissingFunction {
+                    pc: 2,
+                    name: "second".into()
+                },
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            "unknown host function `first` at pc 0\nunknown host function `second` at pc 2"
+        );
+    }
+}